@@ -4,6 +4,7 @@ move-profile-lower = Move { $item-name } Down
 move-profile-to-bottom = Move { $item-name } to Bottom
 hide-profile = Hide { $item-name }
 hide-app = Hide all profiles of { $app-name }
+copy-launch-command = Copy Launch Command
 
 settings-tab-advanced = Advanced
 settings-tab-general = General
@@ -0,0 +1,169 @@
+// Suggests routing rules from the accounts profiles are signed in to,
+// e.g a profile signed in as you@acme.com gets "*.acme.com"
+
+// personal mailbox providers; their domain says nothing about which sites belong to the account
+const PERSONAL_EMAIL_DOMAINS: [&str; 14] = [
+    "gmail.com",
+    "googlemail.com",
+    "outlook.com",
+    "hotmail.com",
+    "live.com",
+    "msn.com",
+    "yahoo.com",
+    "icloud.com",
+    "me.com",
+    "mac.com",
+    "proton.me",
+    "protonmail.com",
+    "gmx.com",
+    "fastmail.com",
+];
+
+pub struct ProfileAccount {
+    pub profile_unique_id: String,
+    pub email: Option<String>,
+    // organization domain, e.g "acme.com"
+    pub domain: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RuleSuggestion {
+    pub url_pattern: String,
+    pub profile_unique_id: String,
+}
+
+// "you@acme.com" -> Some("acme.com"), "you@gmail.com" -> None
+pub fn organization_domain_from_email(email: &str) -> Option<String> {
+    let (_, domain) = email.rsplit_once('@')?;
+    let domain = domain.trim().to_lowercase();
+    if domain.is_empty() || PERSONAL_EMAIL_DOMAINS.contains(&domain.as_str()) {
+        return None;
+    }
+    return Some(domain);
+}
+
+// Suggestions which already exist as rules are skipped.
+// Domains of several profiles are skipped as well, because the rule would be ambiguous.
+pub fn suggest_rules(
+    accounts: &[ProfileAccount],
+    existing_url_patterns: &[String],
+) -> Vec<RuleSuggestion> {
+    let mut suggestions: Vec<RuleSuggestion> = Vec::new();
+
+    for account in accounts {
+        if let Some(domain) = &account.domain {
+            let domain_profiles_count = accounts
+                .iter()
+                .filter(|a| a.domain.as_ref() == Some(domain))
+                .count();
+            if domain_profiles_count == 1 {
+                suggestions.push(RuleSuggestion {
+                    url_pattern: format!("*.{}", domain),
+                    profile_unique_id: account.profile_unique_id.clone(),
+                });
+            }
+        }
+
+        // Google apps pick the account from the authuser query parameter,
+        // which can be anywhere in the query, e.g "?usp=sharing&authuser=you@acme.com"
+        if let Some(email) = &account.email {
+            suggestions.push(RuleSuggestion {
+                url_pattern: format!("docs.google.com/**?**authuser={}**", email),
+                profile_unique_id: account.profile_unique_id.clone(),
+            });
+        }
+    }
+
+    suggestions.retain(|suggestion| !existing_url_patterns.contains(&suggestion.url_pattern));
+    return suggestions;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::url_rule;
+
+    fn account(
+        profile_unique_id: &str,
+        email: Option<&str>,
+        domain: Option<&str>,
+    ) -> ProfileAccount {
+        return ProfileAccount {
+            profile_unique_id: profile_unique_id.to_string(),
+            email: email.map(|e| e.to_string()),
+            domain: domain.map(|d| d.to_string()),
+        };
+    }
+
+    fn suggestion(url_pattern: &str, profile_unique_id: &str) -> RuleSuggestion {
+        return RuleSuggestion {
+            url_pattern: url_pattern.to_string(),
+            profile_unique_id: profile_unique_id.to_string(),
+        };
+    }
+
+    #[test]
+    fn test_organization_domain_from_email() {
+        assert_eq!(
+            organization_domain_from_email("You@Acme.com"),
+            Some("acme.com".to_string())
+        );
+        assert_eq!(organization_domain_from_email("you@gmail.com"), None);
+        assert_eq!(organization_domain_from_email("not-an-email"), None);
+    }
+
+    #[test]
+    fn test_suggest_rules() {
+        let accounts = vec![
+            account("chrome#Profile 1", Some("you@acme.com"), Some("acme.com")),
+            account("chrome#Default", Some("you@gmail.com"), None),
+            account("chrome#Profile 2", None, None),
+        ];
+
+        assert_eq!(
+            suggest_rules(&accounts, &[]),
+            vec![
+                suggestion("*.acme.com", "chrome#Profile 1"),
+                suggestion(
+                    "docs.google.com/**?**authuser=you@acme.com**",
+                    "chrome#Profile 1"
+                ),
+                suggestion("docs.google.com/**?**authuser=you@gmail.com**", "chrome#Default"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suggest_rules_skips_existing_and_ambiguous() {
+        let accounts = vec![
+            account("chrome#Profile 1", Some("a@acme.com"), Some("acme.com")),
+            account("firefox#work", Some("b@acme.com"), Some("acme.com")),
+        ];
+        let existing_url_patterns = vec!["docs.google.com/**?**authuser=a@acme.com**".to_string()];
+
+        assert_eq!(
+            suggest_rules(&accounts, &existing_url_patterns),
+            vec![suggestion(
+                "docs.google.com/**?**authuser=b@acme.com**",
+                "firefox#work"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_suggested_google_rule_matches_docs_url() {
+        let accounts = vec![account("chrome#Profile 1", Some("you@acme.com"), None)];
+        let url_pattern = suggest_rules(&accounts, &[])[0].url_pattern.clone();
+        let url_glob_matcher = url_rule::to_url_matcher(url_pattern.as_str()).to_glob_matcher();
+
+        assert!(url_glob_matcher.url_str_matches(
+            "https://docs.google.com/document/d/1tLqvkY7Ppfv0MQ7wZEqGzmAHp5UIe-5OzpG9I_0-Cd8/edit?usp=sharing&authuser=you@acme.com"
+        ));
+        assert!(url_glob_matcher.url_str_matches(
+            "https://docs.google.com/spreadsheets/d/1BxiMVs0XRA5nFMdKvBdBZjgmUUqptlbs74OgvE2upms/edit?authuser=you@acme.com&usp=drive_link#gid=0"
+        ));
+        assert!(!url_glob_matcher.url_str_matches(
+            "https://docs.google.com/document/d/1tLqvkY7Ppfv0MQ7wZEqGzmAHp5UIe-5OzpG9I_0-Cd8/edit?usp=sharing&authuser=other@acme.com"
+        ));
+    }
+}
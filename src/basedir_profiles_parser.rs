@@ -0,0 +1,223 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, info};
+
+use crate::{InstalledAppProfiles, InstalledAppProfilesType, InstalledBrowserProfile};
+
+// Browsers which keep every profile in a separate base directory,
+// instead of an index file like Chromium's `Local State` or Firefox's `profiles.ini`:
+//   qutebrowser: qutebrowser --basedir <dir>
+//   GNOME Web:   epiphany --profile=<dir>
+//
+// There is no common location for the base dirs, so they are configured in config.json.
+// Base dir patterns are absolute paths, where the last component may contain a single `*`,
+// e.g "/home/user/.local/share/org.gnome.Epiphany.WebApp_*" matches every web app profile dir.
+pub fn add_configured_base_dirs(
+    profiles: &mut InstalledAppProfiles,
+    base_dir_patterns: &[PathBuf],
+) {
+    let base_dirs: Vec<PathBuf> = base_dir_patterns
+        .iter()
+        .flat_map(|pattern| expand_base_dir_pattern(pattern.as_path()))
+        .collect();
+
+    if base_dirs.is_empty() {
+        return;
+    }
+
+    // running the browser without a base dir argument uses its standard dirs
+    if profiles.profiles_type == InstalledAppProfilesType::PlaceholderProfiles {
+        profiles.profiles_type = InstalledAppProfilesType::RealProfiles;
+        profiles.profiles = vec![default_profile()];
+    }
+
+    for profile in base_dirs_to_profiles(&base_dirs) {
+        let already_found = profiles
+            .profiles
+            .iter()
+            .any(|p| p.profile_cli_arg_value == profile.profile_cli_arg_value);
+        if !already_found {
+            profiles.profiles.push(profile);
+        }
+    }
+}
+
+fn default_profile() -> InstalledBrowserProfile {
+    return base_dir_profile("", "Default".to_string());
+}
+
+fn base_dirs_to_profiles(base_dirs: &[PathBuf]) -> Vec<InstalledBrowserProfile> {
+    return base_dirs
+        .iter()
+        .map(|base_dir| {
+            let profile_name = base_dir_display_name(base_dir.as_path());
+            debug!("Base dir profile {}: {}", profile_name, base_dir.display());
+            base_dir_profile(base_dir.to_str().unwrap_or_default(), profile_name)
+        })
+        .collect();
+}
+
+fn base_dir_profile(base_dir: &str, profile_name: String) -> InstalledBrowserProfile {
+    return InstalledBrowserProfile {
+        profile_cli_arg_value: base_dir.to_string(),
+        profile_cli_container_name: None,
+        profile_name: profile_name,
+        profile_icon: None,
+        profile_restricted_url_patterns: vec![],
+        locked_to_other_install: false,
+        profile_container_color: None,
+        profile_container_icon: None,
+        profile_account_email: None,
+        profile_account_domain: None,
+        profile_last_used: None,
+        profile_is_browser_default: false,
+        profile_dir: None,
+        profile_launch_by_dir: false,
+        profile_is_temporary: false,
+    };
+}
+
+// GNOME Web keeps the web app's .desktop file in its profile dir, use its name if possible
+fn base_dir_display_name(base_dir: &Path) -> String {
+    let desktop_file_name_maybe = fs::read_dir(base_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| parse_desktop_file_name(content.as_str()));
+
+    if let Some(desktop_file_name) = desktop_file_name_maybe {
+        return desktop_file_name;
+    }
+
+    return base_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| base_dir.display().to_string());
+}
+
+fn parse_desktop_file_name(content: &str) -> Option<String> {
+    return content
+        .lines()
+        .skip_while(|line| line.trim() != "[Desktop Entry]")
+        .find_map(|line| line.strip_prefix("Name="))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+}
+
+// "/a/b"   -> ["/a/b"] if it is a directory
+// "/a/b_*" -> every directory in "/a" which starts with "b_"
+fn expand_base_dir_pattern(pattern: &Path) -> Vec<PathBuf> {
+    let file_name = pattern
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let Some((prefix, suffix)) = file_name.split_once('*') else {
+        return if pattern.is_dir() {
+            vec![pattern.to_path_buf()]
+        } else {
+            info!("Could not find base dir {}", pattern.display());
+            vec![]
+        };
+    };
+
+    let parent_dir = pattern.parent().unwrap_or(Path::new(""));
+    let Ok(entries) = fs::read_dir(parent_dir) else {
+        info!("Could not list base dirs in {}", parent_dir.display());
+        return vec![];
+    };
+
+    let mut base_dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        })
+        .collect();
+    base_dirs.sort();
+
+    return base_dirs;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_dir;
+
+    #[test]
+    fn test_expand_base_dir_pattern() {
+        let test_dir = create_test_dir("basedir", "expand");
+        fs::create_dir_all(test_dir.join("org.gnome.Epiphany.WebApp_b")).unwrap();
+        fs::create_dir_all(test_dir.join("org.gnome.Epiphany.WebApp_a")).unwrap();
+        fs::create_dir_all(test_dir.join("epiphany")).unwrap();
+        fs::write(test_dir.join("org.gnome.Epiphany.WebApp_file"), "").unwrap();
+
+        assert_eq!(
+            expand_base_dir_pattern(test_dir.join("org.gnome.Epiphany.WebApp_*").as_path()),
+            vec![
+                test_dir.join("org.gnome.Epiphany.WebApp_a"),
+                test_dir.join("org.gnome.Epiphany.WebApp_b"),
+            ]
+        );
+        assert_eq!(
+            expand_base_dir_pattern(test_dir.join("epiphany").as_path()),
+            vec![test_dir.join("epiphany")]
+        );
+        assert!(expand_base_dir_pattern(test_dir.join("missing").as_path()).is_empty());
+        assert!(expand_base_dir_pattern(test_dir.join("missing/*").as_path()).is_empty());
+    }
+
+    #[test]
+    fn test_add_configured_base_dirs() {
+        let test_dir = create_test_dir("basedir", "find");
+        let web_app_dir = test_dir.join("org.gnome.Epiphany.WebApp_123abc");
+        fs::create_dir_all(web_app_dir.as_path()).unwrap();
+        fs::write(
+            web_app_dir.join("org.gnome.Epiphany.WebApp_123abc.desktop"),
+            "[Desktop Entry]\nName=Element\nExec=epiphany --application-mode\n",
+        )
+        .unwrap();
+        fs::create_dir_all(test_dir.join("work")).unwrap();
+
+        let mut profiles = InstalledAppProfiles {
+            profiles_type: InstalledAppProfilesType::PlaceholderProfiles,
+            profiles: vec![],
+        };
+        add_configured_base_dirs(&mut profiles, &[test_dir.join("missing_*")]);
+        assert_eq!(
+            profiles.profiles_type,
+            InstalledAppProfilesType::PlaceholderProfiles
+        );
+
+        let base_dirs = [
+            test_dir.join("org.gnome.Epiphany.WebApp_*"),
+            test_dir.join("work"),
+        ];
+        add_configured_base_dirs(&mut profiles, &base_dirs);
+        // already added base dirs are not added again
+        add_configured_base_dirs(&mut profiles, &base_dirs);
+        assert_eq!(profiles.profiles_type, InstalledAppProfilesType::RealProfiles);
+        let names: Vec<&str> = profiles
+            .profiles
+            .iter()
+            .map(|p| p.profile_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Default", "Element", "work"]);
+        assert_eq!(profiles.profiles[0].profile_cli_arg_value, "");
+        assert_eq!(
+            profiles.profiles[1].profile_cli_arg_value,
+            web_app_dir.to_str().unwrap()
+        );
+    }
+}
@@ -1,14 +1,14 @@
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufReader, Read};
+use std::fs;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tracing::{info, warn};
 use url::form_urlencoded::byte_serialize;
 use url::Url;
 
 use crate::url_rule::UrlGlobMatcher;
+use crate::url_transform::UrlTransform;
 use crate::{
     chromium_profiles_parser, firefox_profiles_parser, paths, slack_profiles_parser,
     slack_url_parser, url_rule, CommonBrowserProfile, InstalledAppProfiles,
@@ -19,24 +19,44 @@ use crate::{
 // All other apps will be the "default" supported app implementation
 pub struct SupportedAppRepository {
     snap_base: PathBuf,
+    flatpak_base: PathBuf,
     chromium_user_dir_base: PathBuf,
     firefox_user_dir_base: PathBuf,
     supported_apps: HashMap<String, SupportedApp>,
+    schemes: Vec<SchemeConfig>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
-enum AppOS {
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum AppOS {
     LINUX,
     MAC,
     WINDOWS,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+impl AppOS {
+    // the OS this app is running on
+    pub fn current() -> Self {
+        #[cfg(target_os = "macos")]
+        return AppOS::MAC;
+
+        #[cfg(target_os = "linux")]
+        return AppOS::LINUX;
+
+        #[cfg(target_os = "windows")]
+        return AppOS::WINDOWS;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 enum AppKind {
     GENERIC,
     CHROMIUM,
     FIREFOX,
+    // Firefox-based mail client, gets mailto urls after `-compose`
+    THUNDERBIRD,
     SLACK,
+    // every profile is a separate base directory (qutebrowser, GNOME Web)
+    BASEDIR,
     LINEAR,
     MIMESTREAM,
     NOTION,
@@ -46,9 +66,61 @@ enum AppKind {
     ZOOM,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
 struct AppConfigRepository {
     apps: Vec<AppConfig>,
+    schemes: Vec<SchemeConfig>,
+}
+
+impl AppConfigRepository {
+    // entries of other replace entries of self with the same (os, id) or scheme
+    fn merge(&mut self, other: AppConfigRepository) {
+        for app_config in other.apps {
+            let existing_index_maybe = self
+                .apps
+                .iter()
+                .position(|a| a.os == app_config.os && a.id == app_config.id);
+            if let Some(existing_index) = existing_index_maybe {
+                self.apps[existing_index] = app_config;
+            } else {
+                self.apps.push(app_config);
+            }
+        }
+
+        for scheme_config in other.schemes {
+            let existing_index_maybe = self
+                .schemes
+                .iter()
+                .position(|s| s.scheme == scheme_config.scheme);
+            if let Some(existing_index) = existing_index_maybe {
+                self.schemes[existing_index] = scheme_config;
+            } else {
+                self.schemes.push(scheme_config);
+            }
+        }
+    }
+}
+
+// url scheme for which to discover handler apps (e.g zoommtg://),
+// and the http(s) domains which those apps should be offered for
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SchemeConfig {
+    scheme: String,
+    // apps of this kind get restricted_domains, even if they don't register the scheme
+    kind: Option<AppKind>,
+    restricted_domains: Vec<String>,
+}
+
+impl SchemeConfig {
+    pub fn get_scheme(&self) -> &str {
+        return self.scheme.as_str();
+    }
+
+    pub fn get_restricted_domains(&self) -> &Vec<String> {
+        return &self.restricted_domains;
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -61,6 +133,15 @@ struct AppConfig {
     id: String,
     config_dir_relative: String,
     snap_id: Option<String>,
+    url_transform: Option<UrlTransform>,
+    // overrides restricted_domains of the scheme of this app kind
+    restricted_domains: Vec<String>,
+    // override the launch arguments of the app kind, e.g incognito_args = ["--inprivate"];
+    // in profile_args "{profile}" is replaced with the profile, e.g ["--profile={profile}"]
+    profile_args: Option<Vec<String>>,
+    incognito_args: Option<Vec<String>>,
+    new_window_args: Option<Vec<String>>,
+    url_as_first_arg: Option<bool>,
 }
 
 impl Default for AppConfig {
@@ -71,6 +152,12 @@ impl Default for AppConfig {
             id: "".to_string(),
             config_dir_relative: "".to_string(),
             snap_id: None,
+            url_transform: None,
+            restricted_domains: vec![],
+            profile_args: None,
+            incognito_args: None,
+            new_window_args: None,
+            url_as_first_arg: None,
         }
     }
 }
@@ -79,14 +166,32 @@ impl SupportedAppRepository {
     pub fn new() -> Self {
         let mut repository = Self {
             snap_base: paths::get_snap_root(),
+            flatpak_base: paths::get_flatpak_root(),
             chromium_user_dir_base: paths::get_chrome_user_dir_root(),
             firefox_user_dir_base: paths::get_firefox_user_dir_root(),
             supported_apps: HashMap::new(),
+            schemes: vec![],
         };
         repository.generate_app_id_to_supported_app();
         return repository;
     }
 
+    // the first app of the given repository file content, with fixed base dirs
+    #[cfg(test)]
+    pub(crate) fn create_app_from_toml(repository_toml: &str) -> SupportedApp {
+        let mut repository = Self {
+            snap_base: PathBuf::from("/snap"),
+            flatpak_base: PathBuf::from("/var/lib/flatpak/app"),
+            chromium_user_dir_base: PathBuf::from("/home/user/.config"),
+            firefox_user_dir_base: PathBuf::from("/home/user"),
+            supported_apps: HashMap::new(),
+            schemes: vec![],
+        };
+        let app_config_repository: AppConfigRepository = toml::from_str(repository_toml).unwrap();
+        let app_config = app_config_repository.apps.into_iter().next().unwrap();
+        return repository.create_app_from_app_config(app_config);
+    }
+
     pub fn get_or_generate(
         &self,
         app_id_str: &str,
@@ -102,35 +207,65 @@ impl SupportedAppRepository {
             });
     }
 
+    pub fn get_schemes(&self) -> &Vec<SchemeConfig> {
+        return &self.schemes;
+    }
+
     fn add(&mut self, supported_app: SupportedApp) -> &mut SupportedAppRepository {
         self.supported_apps
             .insert(supported_app.get_app_id().to_string(), supported_app);
         return self;
     }
 
-    fn load_repository(&self) -> Vec<AppConfig> {
+    // bundled repository, with user's repository file merged on top of it
+    fn load_repository(&self) -> AppConfigRepository {
         let repository_toml_path = paths::get_repository_toml_path();
+        let mut repository = Self::load_repository_file(repository_toml_path.as_path());
+
+        let user_repository_toml_path = paths::get_user_repository_toml_path();
+        if user_repository_toml_path.exists() {
+            let user_repository = Self::load_repository_file(user_repository_toml_path.as_path());
+            repository.merge(user_repository);
+        }
+
+        return repository;
+    }
 
+    fn load_repository_file(repository_toml_path: &Path) -> AppConfigRepository {
         info!("Repository: {}", repository_toml_path.display());
 
         if !repository_toml_path.exists() {
-            return vec![];
+            return AppConfigRepository::default();
         }
 
-        // Open the file in read-only mode with buffer.
-        let file = File::open(repository_toml_path.as_path()).unwrap();
-        let mut reader = BufReader::new(file);
+        let data_result = fs::read_to_string(repository_toml_path);
+        if let Err(e) = data_result {
+            warn!(
+                "Could not read repository file {}: {}",
+                repository_toml_path.display(),
+                e
+            );
+            return AppConfigRepository::default();
+        }
+        let data = data_result.unwrap();
+
+        let result: Result<AppConfigRepository, _> = toml::from_str(data.as_ref());
+        if let Err(e) = result {
+            warn!(
+                "Could not parse repository file {}, ignoring it: {}",
+                repository_toml_path.display(),
+                e
+            );
+            return AppConfigRepository::default();
+        }
 
-        let mut data: String = String::new();
-        reader.read_to_string(&mut data).unwrap();
-        let result = toml::from_str(data.as_ref());
-        let repository: AppConfigRepository = result.unwrap();
-        return repository.apps;
+        return result.unwrap();
     }
 
     fn add_apps_from_repository_file(&mut self) {
-        let app_configs = self.load_repository();
-        for app_config in app_configs {
+        let repository = self.load_repository();
+        self.schemes = repository.schemes;
+        for app_config in repository.apps {
             let app = self.create_app_from_app_config(app_config);
             self.add(app);
         }
@@ -195,12 +330,17 @@ impl SupportedAppRepository {
             self.snap_config_dir_absolute_path(linux_snap_id, linux_config_dir_relative);
 
         let app_id = AppIdentifier::new_linux(linux_desktop_id);
-        let app = Self::firefox_based_app(
+        let mut app = Self::firefox_based_app(
             app_id,
             app_config_dir.config_dir_absolute(),
             snap_app_config_dir_absolute.clone(),
             PathBuf::from(""),
         );
+        // flatpak apps have their own home in ~/.var/app/<app-id>
+        app.flatpak_app_config_dir_absolute = self.flatpak_config_dir_absolute_path(
+            linux_desktop_id,
+            Path::new(linux_config_dir_relative),
+        );
         return app;
     }
 
@@ -216,11 +356,13 @@ impl SupportedAppRepository {
         let snap_id_owned = app_config.snap_id.unwrap_or_default();
         let linux_snap_id = snap_id_owned.as_str();
 
-        let app = match app_config.kind {
-            AppKind::GENERIC => {
-                let restricted_domain_patterns = vec![];
-                Self::create_generic_app(app_config.os, app_id, restricted_domain_patterns)
-            }
+        let restricted_domain_patterns = if !app_config.restricted_domains.is_empty() {
+            app_config.restricted_domains.clone()
+        } else {
+            self.get_restricted_domains_of_kind(app_config.kind)
+        };
+
+        let mut app = match app_config.kind {
             AppKind::CHROMIUM => match app_config.os {
                 AppOS::LINUX => {
                     self.create_chromium_based_linux(app_id, linux_snap_id, config_dir_relative)
@@ -235,62 +377,76 @@ impl SupportedAppRepository {
                 AppOS::MAC => self.create_firefox_based_mac(app_id, config_dir_relative),
                 AppOS::WINDOWS => self.create_firefox_based_windows(app_id, config_dir_relative),
             },
-            AppKind::LINEAR => {
-                let restricted_domain_patterns = vec!["linear.app".to_string()];
-                Self::create_generic_app(app_config.os, app_id, restricted_domain_patterns)
-            }
-            AppKind::MIMESTREAM => {
-                let restricted_domain_patterns = vec!["links.mimestream.com".to_string()];
-                Self::create_generic_app(app_config.os, app_id, restricted_domain_patterns)
-            }
-            AppKind::NOTION => {
-                let restricted_domain_patterns =
-                    vec!["notion.so".to_string(), "www.notion.so".to_string()];
-                Self::create_generic_app(app_config.os, app_id, restricted_domain_patterns)
+            AppKind::THUNDERBIRD => {
+                let firefox_based_app = match app_config.os {
+                    AppOS::LINUX => {
+                        self.create_firefox_based_linux(app_id, linux_snap_id, config_dir_relative)
+                    }
+                    AppOS::MAC => self.create_firefox_based_mac(app_id, config_dir_relative),
+                    AppOS::WINDOWS => {
+                        self.create_firefox_based_windows(app_id, config_dir_relative)
+                    }
+                };
+                Self::thunderbird_based_app(firefox_based_app)
             }
             AppKind::SLACK => match app_config.os {
                 AppOS::LINUX => self.create_slack_linux(app_id, linux_snap_id, config_dir_relative),
                 AppOS::MAC => self.create_slack_mac(app_id, config_dir_relative),
                 AppOS::WINDOWS => self.create_slack_windows(app_id, config_dir_relative),
             },
-            AppKind::SPOTIFY => {
-                let restricted_domain_patterns = vec!["open.spotify.com".to_string()];
-                Self::create_generic_app_with_url(
-                    app_config.os,
-                    app_id,
-                    restricted_domain_patterns,
-                    convert_spotify_uri,
-                )
-            }
-            AppKind::TELEGRAM => {
-                let restricted_domain_patterns = vec!["t.me".to_string()];
-                Self::create_generic_app(app_config.os, app_id, restricted_domain_patterns)
-            }
-            AppKind::WORKFLOWY => {
-                let restricted_domain_patterns = vec!["workflowy.com".to_string()];
-                Self::create_generic_app_with_url(
-                    app_config.os,
-                    app_id,
-                    restricted_domain_patterns,
-                    convert_workflowy_uri,
-                )
-            }
-            AppKind::ZOOM => {
-                let restricted_domain_patterns = vec![
-                    "zoom.us".to_string(),
-                    "eu01web.zoom.us".to_string(),
-                    "us02web.zoom.us".to_string(),
-                    "us03web.zoom.us".to_string(),
-                    "us04web.zoom.us".to_string(),
-                    "us05web.zoom.us".to_string(),
-                    "us06web.zoom.us".to_string(),
-                    "us07web.zoom.us".to_string(),
-                ];
-
-                Self::create_generic_app(app_config.os, app_id, restricted_domain_patterns)
-            }
+            AppKind::BASEDIR => Self::create_basedir_app(app_config.os, app_id),
+            AppKind::GENERIC
+            | AppKind::LINEAR
+            | AppKind::MIMESTREAM
+            | AppKind::NOTION
+            | AppKind::SPOTIFY
+            | AppKind::TELEGRAM
+            | AppKind::WORKFLOWY
+            | AppKind::ZOOM => Self::create_generic_app(app_config.os, app_id, vec![]),
         };
 
+        if !restricted_domain_patterns.is_empty() {
+            app.restricted_url_matchers =
+                Self::generate_restricted_hostname_matchers(&restricted_domain_patterns);
+        }
+
+        if app_config.url_transform.is_some() {
+            app.url_transform = app_config.url_transform;
+        }
+
+        if let Some(profile_args) = app_config.profile_args {
+            app.profile_args = profile_args;
+        }
+        if let Some(incognito_args) = app_config.incognito_args {
+            // empty list means that the app has no private mode
+            app.incognito_args = incognito_args;
+        }
+        if let Some(new_window_args) = app_config.new_window_args {
+            app.new_window_args = new_window_args;
+        }
+        if let Some(url_as_first_arg) = app_config.url_as_first_arg {
+            app.url_as_first_arg = url_as_first_arg;
+        }
+
+        return app;
+    }
+
+    fn get_restricted_domains_of_kind(&self, kind: AppKind) -> Vec<String> {
+        return self
+            .schemes
+            .iter()
+            .filter(|scheme| scheme.kind == Some(kind))
+            .flat_map(|scheme| scheme.restricted_domains.clone())
+            .collect();
+    }
+
+    // profiles are the base dirs configured in config.json
+    fn create_basedir_app(os: AppOS, app_id: &str) -> SupportedApp {
+        let app_identifier = Self::create_app_identifier(os, app_id);
+        let mut app = Self::generic_app(app_identifier, vec![]);
+        app.has_profile_base_dirs = true;
+        // qutebrowser style, other browsers override it in the repository file
+        app.profile_args = vec!["--basedir".to_string(), "{profile}".to_string()];
         return app;
     }
 
@@ -309,13 +465,19 @@ impl SupportedAppRepository {
             self.snap_config_dir_absolute_path(linux_snap_id, linux_config_dir_relative);
 
         let app_id = AppIdentifier::new_linux(linux_desktop_id);
-        let app = Self::slack_app(
+        let mut app = Self::slack_app(
             app_id,
             app_config_dir.config_dir_absolute(),
             snap_app_config_dir_absolute.clone(),
             PathBuf::from(""),
         );
 
+        // flatpak apps have their own ~/.config in ~/.var/app/<app-id>/config
+        app.flatpak_app_config_dir_absolute = self.flatpak_config_dir_absolute_path(
+            linux_desktop_id,
+            Path::new("config").join(linux_config_dir_relative).as_path(),
+        );
+
         return app;
     }
 
@@ -423,12 +585,17 @@ impl SupportedAppRepository {
             self.snap_config_dir_absolute_path(linux_snap_id, linux_config_dir_relative);
 
         let app_id = AppIdentifier::new_linux(linux_desktop_id);
-        let app = Self::chromium_based_app(
+        let mut app = Self::chromium_based_app(
             app_id,
             app_config_dir.config_dir_absolute(),
             snap_app_config_dir_absolute.clone(),
             PathBuf::from(""),
         );
+        // flatpak apps have their own ~/.config in ~/.var/app/<app-id>/config
+        app.flatpak_app_config_dir_absolute = self.flatpak_config_dir_absolute_path(
+            linux_desktop_id,
+            Path::new("config").join(linux_config_dir_relative).as_path(),
+        );
         return app;
     }
 
@@ -445,6 +612,18 @@ impl SupportedAppRepository {
         return config_dir_absolute;
     }
 
+    // flatpak app id is the same as the desktop id
+    fn flatpak_config_dir_absolute_path(
+        &self,
+        flatpak_app_id: &str,
+        config_dir_relative_to_app_home: &Path,
+    ) -> PathBuf {
+        return self
+            .flatpak_base
+            .join(flatpak_app_id)
+            .join(config_dir_relative_to_app_home);
+    }
+
     fn chromium_based_app(
         app_id: AppIdentifier,
         app_config_dir_absolute: PathBuf,
@@ -457,14 +636,21 @@ impl SupportedAppRepository {
             app_id: app_id,
             app_config_dir_absolute: app_config_dir_absolute,
             snap_app_config_dir_absolute: snap_app_config_dir_absolute,
+            flatpak_app_config_dir_absolute: PathBuf::new(),
             macos_sandbox_app_config_dir_absolute: macos_sandbox_app_config_dir_absolute,
             find_profiles_fn: Some(chromium_profiles_parser::find_chromium_profiles),
             restricted_url_matchers: vec![],
-            profile_args_fn: |profile_cli_arg_value| {
-                vec![format!("--profile-directory={}", profile_cli_arg_value)]
-            },
+            has_profile_base_dirs: false,
+            profile_args: vec!["--profile-directory={profile}".to_string()],
+            profile_dir_args: vec![],
+            new_instance_args: vec![],
+            user_data_dir_args: vec!["--user-data-dir={user_data_dir}".to_string()],
             incognito_args: vec!["--incognito".to_string()],
+            new_window_args: vec![],
+            app_window_arg: Some("--app={url}".to_string()),
+            mailto_args: vec![],
             url_transform_fn: chromium_url_transform_fn,
+            url_transform: None,
             url_as_first_arg: true,
         }
     }
@@ -495,44 +681,43 @@ impl SupportedAppRepository {
             app_id: app_id,
             app_config_dir_absolute: app_config_dir_absolute,
             snap_app_config_dir_absolute: snap_app_config_dir_absolute,
+            flatpak_app_config_dir_absolute: PathBuf::new(),
             macos_sandbox_app_config_dir_absolute: macos_sandbox_app_config_dir_absolute,
             find_profiles_fn: Some(firefox_profiles_parser::find_firefox_profiles),
             restricted_url_matchers: vec![],
-            profile_args_fn: |profile_cli_arg_value| {
-                vec!["-P".to_string(), profile_cli_arg_value.to_string()]
-            },
+            has_profile_base_dirs: false,
+            profile_args: vec!["-P".to_string(), "{profile}".to_string()],
+            profile_dir_args: vec!["--profile".to_string(), "{profile_dir}".to_string()],
+            new_instance_args: vec!["--new-instance".to_string()],
+            user_data_dir_args: vec![],
             incognito_args: vec!["--private-window".to_string()],
+            new_window_args: vec![],
+            app_window_arg: None,
+            mailto_args: vec![],
             url_transform_fn: firefox_url_transform_fn,
+            url_transform: None,
             url_as_first_arg: true,
         }
     }
 
-    fn create_generic_app(
-        os: AppOS,
-        app_id: &str,
-        restricted_domain_patterns: Vec<String>,
-    ) -> SupportedApp {
-        let url_transform_fn: UrlTransformFn = |_, url| url.to_string();
-        return Self::create_generic_app_with_url(
-            os,
-            app_id,
-            restricted_domain_patterns,
-            url_transform_fn,
-        );
+    // same profiles.ini and launch arguments as Firefox, but no private windows or containers
+    fn thunderbird_based_app(firefox_based_app: SupportedApp) -> SupportedApp {
+        let mut app = firefox_based_app;
+        app.incognito_args = vec![];
+        // the profile lock check and separate instances are only for Firefox
+        app.new_instance_args = vec![];
+        app.mailto_args = vec!["-compose".to_string()];
+        app.url_transform_fn = |_, url| url.to_string();
+        return app;
     }
 
-    fn create_generic_app_with_url(
+    fn create_generic_app(
         os: AppOS,
         app_id: &str,
         restricted_domain_patterns: Vec<String>,
-        url_transform_fn: UrlTransformFn,
     ) -> SupportedApp {
         let app_identifier = Self::create_app_identifier(os, app_id);
-        let app = Self::generic_app_with_url(
-            app_identifier,
-            restricted_domain_patterns,
-            url_transform_fn,
-        );
+        let app = Self::generic_app(app_identifier, restricted_domain_patterns);
         return app;
     }
 
@@ -560,12 +745,21 @@ impl SupportedAppRepository {
             app_id: app_id,
             app_config_dir_absolute: PathBuf::new(),
             snap_app_config_dir_absolute: PathBuf::new(),
+            flatpak_app_config_dir_absolute: PathBuf::new(),
             macos_sandbox_app_config_dir_absolute: PathBuf::new(),
             find_profiles_fn: None,
             restricted_url_matchers: restricted_url_matchers,
-            profile_args_fn: |_profile_cli_arg_value| vec![],
+            has_profile_base_dirs: false,
+            profile_args: vec![],
+            profile_dir_args: vec![],
+            new_instance_args: vec![],
+            user_data_dir_args: vec![],
             incognito_args: vec![],
+            new_window_args: vec![],
+            app_window_arg: None,
+            mailto_args: vec![],
             url_transform_fn: url_transform_fn,
+            url_transform: None,
             url_as_first_arg: false,
         }
     }
@@ -591,24 +785,27 @@ impl SupportedAppRepository {
         snap_app_config_dir_absolute: PathBuf,
         macos_sandbox_app_config_dir_absolute: PathBuf,
     ) -> SupportedApp {
+        // restricted_url_matchers are set from repository file
         // todo: filter only specific profiles? But per profile?
-        let restricted_domain_patterns = vec![
-            "*.slack.com".to_string(),
-            "*.enterprise.slack.com".to_string(),
-        ];
-        let restricted_url_matchers =
-            Self::generate_restricted_hostname_matchers(&restricted_domain_patterns);
-
         SupportedApp {
             app_id: app_id,
             app_config_dir_absolute: app_config_dir_absolute,
             snap_app_config_dir_absolute: snap_app_config_dir_absolute,
+            flatpak_app_config_dir_absolute: PathBuf::new(),
             macos_sandbox_app_config_dir_absolute: macos_sandbox_app_config_dir_absolute,
             find_profiles_fn: Some(slack_profiles_parser::find_slack_profiles),
-            restricted_url_matchers: restricted_url_matchers,
-            profile_args_fn: |_profile_cli_arg_value| vec![],
+            restricted_url_matchers: vec![],
+            has_profile_base_dirs: false,
+            profile_args: vec![],
+            profile_dir_args: vec![],
+            new_instance_args: vec![],
+            user_data_dir_args: vec![],
             incognito_args: vec![],
+            new_window_args: vec![],
+            app_window_arg: None,
+            mailto_args: vec![],
             url_transform_fn: convert_slack_uri,
+            url_transform: None,
             url_as_first_arg: false,
         }
     }
@@ -619,6 +816,7 @@ pub struct SupportedApp {
     app_id: AppIdentifier,
     app_config_dir_absolute: PathBuf,
     snap_app_config_dir_absolute: PathBuf,
+    flatpak_app_config_dir_absolute: PathBuf,
     macos_sandbox_app_config_dir_absolute: PathBuf,
     restricted_url_matchers: Vec<UrlGlobMatcher>,
     find_profiles_fn: Option<
@@ -628,9 +826,26 @@ pub struct SupportedApp {
             app_id: &str,
         ) -> Vec<InstalledBrowserProfile>,
     >,
-    profile_args_fn: fn(profile_cli_arg_value: &str) -> Vec<String>,
+    // BASEDIR apps: every profile is a separate base dir, configured in config.json
+    has_profile_base_dirs: bool,
+    // "{profile}" is replaced with the profile cli arg value
+    profile_args: Vec<String>,
+    // launching a profile by its directory, "{profile_dir}" is replaced with the directory
+    profile_dir_args: Vec<String>,
+    // starting a separate instance when the profile is not running yet, e.g "--new-instance"
+    new_instance_args: Vec<String>,
+    // running an extra instance with its own set of profiles, e.g "--user-data-dir={user_data_dir}"
+    user_data_dir_args: Vec<String>,
     incognito_args: Vec<String>,
+    // opening a url in a new window instead of a tab, e.g "--new-window"
+    new_window_args: Vec<String>,
+    // replaces the url argument when opening in app window mode, e.g "--app={url}"
+    app_window_arg: Option<String>,
+    // mail clients: arguments right before the mailto url, e.g "-compose"
+    mailto_args: Vec<String>,
     url_transform_fn: UrlTransformFn,
+    // declarative transform from repository file, applied instead of url_transform_fn
+    url_transform: Option<UrlTransform>,
     url_as_first_arg: bool,
 }
 
@@ -650,9 +865,16 @@ impl SupportedApp {
         return self.app_id.app_id();
     }
 
-    pub fn get_app_config_dir_abs(&self, is_snap: bool, is_macos_sandbox: bool) -> &Path {
+    pub fn get_app_config_dir_abs(
+        &self,
+        is_snap: bool,
+        is_flatpak: bool,
+        is_macos_sandbox: bool,
+    ) -> &Path {
         return if is_snap {
             &self.snap_app_config_dir_absolute.as_path()
+        } else if is_flatpak {
+            &self.flatpak_app_config_dir_absolute.as_path()
         } else if is_macos_sandbox {
             &self.macos_sandbox_app_config_dir_absolute.as_path()
         } else {
@@ -668,10 +890,21 @@ impl SupportedApp {
         &self,
         binary_path: &Path,
         app_config_dir_abs: &Path,
+    ) -> InstalledAppProfiles {
+        return self.find_profiles_of_instance(binary_path, app_config_dir_abs, self.get_app_id());
+    }
+
+    // instance_id keeps the cached profile icons of app instances apart,
+    // e.g of extra Chromium user-data-dirs
+    pub fn find_profiles_of_instance(
+        &self,
+        binary_path: &Path,
+        app_config_dir_abs: &Path,
+        instance_id: &str,
     ) -> InstalledAppProfiles {
         return if let Some(find_profiles_fn) = self.find_profiles_fn {
             let mut browser_profiles: Vec<InstalledBrowserProfile> =
-                find_profiles_fn(app_config_dir_abs, binary_path, self.get_app_id());
+                find_profiles_fn(app_config_dir_abs, binary_path, instance_id);
 
             browser_profiles.sort_by_key(|p| p.profile_name.clone());
             if browser_profiles.is_empty() {
@@ -684,8 +917,54 @@ impl SupportedApp {
         };
     }
 
+    pub fn has_profile_base_dirs(&self) -> bool {
+        return self.has_profile_base_dirs;
+    }
+
+    // e.g ["-P", "{profile}"] -> ["-P", "work"];
+    // no profile args for an empty value, e.g the default profile of BASEDIR apps
     pub fn get_profile_args(&self, profile_cli_arg_value: &str) -> Vec<String> {
-        return (self.profile_args_fn)(profile_cli_arg_value);
+        if profile_cli_arg_value.is_empty() {
+            return vec![];
+        }
+        return self
+            .profile_args
+            .iter()
+            .map(|arg| arg.replace("{profile}", profile_cli_arg_value))
+            .collect();
+    }
+
+    // e.g ["--profile", "{profile_dir}"] -> ["--profile", "/home/user/projects/x/ff-profile"]
+    pub fn get_profile_dir_args(&self, profile_dir: &str) -> Vec<String> {
+        return self
+            .profile_dir_args
+            .iter()
+            .map(|arg| arg.replace("{profile_dir}", profile_dir))
+            .collect();
+    }
+
+    pub fn supports_profile_dir(&self) -> bool {
+        return !self.profile_dir_args.is_empty();
+    }
+
+    pub fn supports_new_instance(&self) -> bool {
+        return !self.new_instance_args.is_empty();
+    }
+
+    pub fn get_new_instance_args(&self) -> &Vec<String> {
+        return &self.new_instance_args;
+    }
+
+    pub fn supports_user_data_dir(&self) -> bool {
+        return !self.user_data_dir_args.is_empty();
+    }
+
+    pub fn get_user_data_dir_args(&self, user_data_dir: &str) -> Vec<String> {
+        return self
+            .user_data_dir_args
+            .iter()
+            .map(|arg| arg.replace("{user_data_dir}", user_data_dir))
+            .collect();
     }
 
     pub fn supports_incognito(&self) -> bool {
@@ -696,11 +975,53 @@ impl SupportedApp {
         return &self.incognito_args;
     }
 
+    pub fn get_new_window_args(&self) -> &Vec<String> {
+        return &self.new_window_args;
+    }
+
+    // false for apps which are not in the repository, which get only the url
+    pub fn has_launch_args(&self) -> bool {
+        return !self.profile_args.is_empty()
+            || !self.profile_dir_args.is_empty()
+            || !self.user_data_dir_args.is_empty()
+            || !self.incognito_args.is_empty()
+            || !self.new_window_args.is_empty()
+            || self.app_window_arg.is_some()
+            || !self.mailto_args.is_empty();
+    }
+
+    pub fn supports_app_window(&self) -> bool {
+        return self.app_window_arg.is_some();
+    }
+
+    // e.g "--app=https://example.com/"; plain url if app window is not supported
+    pub fn get_app_window_arg(&self, url: &str) -> String {
+        return self
+            .app_window_arg
+            .as_ref()
+            .map(|app_window_arg| app_window_arg.replace("{url}", url))
+            .unwrap_or_else(|| url.to_string());
+    }
+
+    pub fn get_mailto_args(&self) -> &Vec<String> {
+        return &self.mailto_args;
+    }
+
+    pub fn is_mail_client(&self) -> bool {
+        return !self.mailto_args.is_empty();
+    }
+
     pub fn get_transformed_url(
         &self,
         common_browser_profile: &CommonBrowserProfile,
         url: &str,
     ) -> String {
+        if let Some(url_transform) = &self.url_transform {
+            return url_transform
+                .transform(url)
+                .unwrap_or_else(|| url.to_string());
+        }
+
         return (self.url_transform_fn)(common_browser_profile, url);
     }
 
@@ -855,42 +1176,146 @@ fn convert_slack_uri(common_browser_profile: &CommonBrowserProfile, url_str: &st
     return slack_url_parser::convert_slack_uri(profile_team_id, profile_team_domain, &url);
 }
 
-fn convert_workflowy_uri(_: &CommonBrowserProfile, url_str: &str) -> String {
-    let result = Url::parse(url_str);
-    if result.is_err() {
-        return "".to_string();
-    }
-    let mut url = result.unwrap();
-    let _ = url.set_scheme("workflowy");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    return url.as_str().to_string();
-}
-
-fn convert_spotify_uri(_: &CommonBrowserProfile, url_str: &str) -> String {
-    let unknown = "spotify:track:2QFvsZEjbketrpCgCNC9Zp".to_string();
+    fn create_app(repository_toml: &str) -> SupportedApp {
+        return SupportedAppRepository::create_app_from_toml(repository_toml);
+    }
 
-    let result = Url::parse(url_str);
-    if result.is_err() {
-        return unknown;
+    #[test]
+    fn test_launch_args_of_app_kind() {
+        let chrome = create_app(
+            r#"
+            [[apps]]
+            id = "google-chrome"
+            config_dir_relative = "google-chrome"
+            kind = "CHROMIUM"
+            os = "LINUX"
+            "#,
+        );
+        assert_eq!(
+            chrome.get_profile_args("Profile 1"),
+            vec!["--profile-directory=Profile 1"]
+        );
+        assert_eq!(chrome.get_incognito_args(), &vec!["--incognito".to_string()]);
+        assert!(chrome.get_new_window_args().is_empty());
+        assert!(chrome.is_url_as_first_arg());
+
+        let qutebrowser = create_app(
+            r#"
+            [[apps]]
+            id = "org.qutebrowser.qutebrowser"
+            kind = "BASEDIR"
+            os = "LINUX"
+            "#,
+        );
+        assert_eq!(
+            qutebrowser.get_profile_args("/home/user/qb/work"),
+            vec!["--basedir", "/home/user/qb/work"]
+        );
+        assert!(qutebrowser.get_profile_args("").is_empty());
+
+        let thunderbird = create_app(
+            r#"
+            [[apps]]
+            id = "thunderbird"
+            config_dir_relative = ".thunderbird"
+            kind = "THUNDERBIRD"
+            os = "LINUX"
+            "#,
+        );
+        assert_eq!(thunderbird.get_profile_args("work"), vec!["-P", "work"]);
+        assert_eq!(thunderbird.get_mailto_args(), &vec!["-compose".to_string()]);
+        assert!(!thunderbird.supports_incognito());
+        assert!(!thunderbird.supports_new_instance());
     }
 
-    let url = result.unwrap();
-    //let host_maybe = url1.host_str();
-    // verify it's "open.spotify.com" ?
-    //let x = url1.path();
+    #[test]
+    fn test_launch_args_overrides() {
+        let edge = create_app(
+            r#"
+            [[apps]]
+            id = "microsoft-edge-dev"
+            config_dir_relative = "microsoft-edge-dev"
+            incognito_args = ["--inprivate"]
+            new_window_args = ["--new-window"]
+            kind = "CHROMIUM"
+            os = "LINUX"
+            "#,
+        );
+        assert_eq!(edge.get_incognito_args(), &vec!["--inprivate".to_string()]);
+        assert_eq!(edge.get_new_window_args(), &vec!["--new-window".to_string()]);
+        assert_eq!(
+            edge.get_profile_args("Default"),
+            vec!["--profile-directory=Default"]
+        );
 
-    let segments_maybe = url.path_segments().map(|c| c.collect::<Vec<_>>());
-    if segments_maybe.is_none() {
-        return unknown;
+        let epiphany = create_app(
+            r#"
+            [[apps]]
+            id = "org.gnome.Epiphany"
+            profile_args = ["--profile={profile}"]
+            kind = "BASEDIR"
+            os = "LINUX"
+            "#,
+        );
+        assert_eq!(
+            epiphany.get_profile_args("/home/user/.local/share/epiphany-work"),
+            vec!["--profile=/home/user/.local/share/epiphany-work"]
+        );
+
+        let no_private_mode = create_app(
+            r#"
+            [[apps]]
+            id = "some-chromium-fork"
+            incognito_args = []
+            url_as_first_arg = false
+            kind = "CHROMIUM"
+            os = "LINUX"
+            "#,
+        );
+        assert!(!no_private_mode.supports_incognito());
+        assert!(!no_private_mode.is_url_as_first_arg());
+
+        let opera = create_app(
+            r#"
+            [[apps]]
+            id = "opera"
+            incognito_args = ["--private"]
+            kind = "GENERIC"
+            os = "LINUX"
+            "#,
+        );
+        assert!(opera.supports_incognito());
+        assert!(opera.get_profile_args("").is_empty());
     }
 
-    let segments = segments_maybe.unwrap();
-    let type_maybe = segments.get(0);
-    let id_maybe = segments.get(1);
+    #[test]
+    fn test_private_mode_args_of_bundled_repository() {
+        let app_config_repository: AppConfigRepository = toml::from_str(include_str!(
+            "../resources/repository/application-repository.toml"
+        ))
+        .unwrap();
+
+        let private_mode_args = |os: AppOS, id: &str| {
+            let app_config = app_config_repository
+                .apps
+                .iter()
+                .find(|app_config| app_config.os == os && app_config.id == id)
+                .unwrap();
+            return app_config.incognito_args.clone().unwrap();
+        };
 
-    let uri_maybe = type_maybe
-        .zip(id_maybe)
-        .map(|(resource_type, resource_id)| format!("spotify:{}:{}", resource_type, resource_id));
+        let inprivate = vec!["--inprivate".to_string()];
+        assert_eq!(private_mode_args(AppOS::LINUX, "microsoft-edge"), inprivate);
+        assert_eq!(private_mode_args(AppOS::LINUX, "microsoft-edge-beta"), inprivate);
+        assert_eq!(private_mode_args(AppOS::WINDOWS, "Microsoft Edge"), inprivate);
 
-    return uri_maybe.unwrap_or_else(|| unknown);
+        let private = vec!["--private".to_string()];
+        assert_eq!(private_mode_args(AppOS::LINUX, "opera"), private);
+        assert_eq!(private_mode_args(AppOS::WINDOWS, "OperaStable"), private);
+        assert_eq!(private_mode_args(AppOS::MAC, "com.operasoftware.Opera"), private);
+    }
 }
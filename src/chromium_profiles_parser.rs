@@ -69,6 +69,16 @@ pub fn find_chromium_profiles(
             profile_name: profile_name,
             profile_icon: profile_icon_path,
             profile_restricted_url_patterns: vec![],
+            locked_to_other_install: false,
+            profile_container_color: None,
+            profile_container_icon: None,
+            profile_account_email: profile.account_email.clone(),
+            profile_account_domain: profile.account_domain.clone(),
+            profile_last_used: profile.last_active_time,
+            profile_is_browser_default: profile.is_last_used,
+            profile_dir: None,
+            profile_launch_by_dir: false,
+            profile_is_temporary: false,
         })
     }
 
@@ -77,30 +87,42 @@ pub fn find_chromium_profiles(
 
 pub struct ChromeInfoCacheMap {
     info_cache_map: Map<String, Value>,
+    // profile dir name the browser opens on start, e.g "Profile 1"
+    last_used_profile_dir_maybe: Option<String>,
 }
 
 impl ChromeInfoCacheMap {
     pub fn new_from_local_state(local_state_file_path: &Path) -> ChromeInfoCacheMap {
-        Self {
-            info_cache_map: Self::profiles_info_cache_map(local_state_file_path),
-        }
-    }
-
-    fn profiles_info_cache_map(local_state_file_path: &Path) -> Map<String, Value> {
         // Open the file in read-only mode with buffer.
         let file = File::open(local_state_file_path).unwrap();
         let reader = BufReader::new(file);
         let v: Value = serde_json::from_reader(reader).unwrap();
-        let profiles = &v["profile"];
+        return Self::new_from_local_state_value(&v);
+    }
+
+    fn new_from_local_state_value(local_state: &Value) -> ChromeInfoCacheMap {
+        let profiles = &local_state["profile"];
         let info_cache = &profiles["info_cache"];
         let info_cache_map = info_cache.as_object().unwrap();
-        return info_cache_map.to_owned();
+        let last_used_profile_dir_maybe = profiles["last_used"]
+            .as_str()
+            .filter(|last_used| !last_used.is_empty())
+            .map(|last_used| last_used.to_string());
+
+        Self {
+            info_cache_map: info_cache_map.to_owned(),
+            last_used_profile_dir_maybe: last_used_profile_dir_maybe,
+        }
     }
 
     pub fn parse_chrome_local_state_profiles(self) -> Vec<ChromeProfilePreferences> {
         let chrome_attributes_finder = ChromeAttributesFinder::new();
         let info_cache_map = self.info_cache_map;
         let profiles_count = info_cache_map.len();
+        // "Default" is opened when Local State has no last used profile
+        let last_used_profile_dir = self
+            .last_used_profile_dir_maybe
+            .unwrap_or_else(|| "Default".to_string());
 
         let mut entries: Vec<ChromeProfileAttributesEntry> = Vec::with_capacity(profiles_count);
         for (dir_name, profile) in info_cache_map {
@@ -110,7 +132,9 @@ impl ChromeInfoCacheMap {
 
         let mut profiles_vec: Vec<ChromeProfilePreferences> = Vec::with_capacity(profiles_count);
         for entry in &entries {
-            profiles_vec.push(entry.get_profile_info(&entries, &chrome_attributes_finder));
+            let mut profile_info = entry.get_profile_info(&entries, &chrome_attributes_finder);
+            profile_info.is_last_used = profile_info.profile_dir_name == last_used_profile_dir;
+            profiles_vec.push(profile_info);
         }
         // constant ordering (well based on name)
         profiles_vec.sort_by(|p1, p2| p1.name.cmp(&p2.name));
@@ -223,10 +247,19 @@ impl ChromeProfileAttributesEntry {
             info!("{}", x1);
         });
 
+        let account_email_maybe = self.get_user_name();
+        // set only for Google Workspace accounts
+        let account_domain_maybe = self.get_hosted_domain();
+        let last_active_time_maybe = self.get_active_time();
+
         return ChromeProfilePreferences {
             profile_dir_name: self.profile_dir.to_string(),
             name: best_name.to_string(),
             avatar_file_path_relative_to_config: profile_avatar_file_path,
+            account_email: account_email_maybe,
+            account_domain: account_domain_maybe,
+            last_active_time: last_active_time_maybe,
+            is_last_used: false,
         };
     }
 
@@ -388,6 +421,32 @@ impl ChromeProfileAttributesEntry {
             .map(|a| a.to_string())
     }*/
 
+    // email of the signed-in account
+    fn get_user_name(&self) -> Option<String> {
+        self.profile["user_name"]
+            .as_str()
+            .filter(|user_name| !user_name.is_empty())
+            .map(|user_name| user_name.to_string())
+    }
+
+    // "acme.com", or "NO_HOSTED_DOMAIN" for consumer accounts
+    fn get_hosted_domain(&self) -> Option<String> {
+        self.profile["hosted_domain"]
+            .as_str()
+            .filter(|hosted_domain| {
+                !hosted_domain.is_empty() && *hosted_domain != "NO_HOSTED_DOMAIN"
+            })
+            .map(|hosted_domain| hosted_domain.to_lowercase())
+    }
+
+    // when the profile was last active, e.g 1718271043.512 (seconds since unix epoch)
+    fn get_active_time(&self) -> Option<u64> {
+        self.profile["active_time"]
+            .as_f64()
+            .filter(|active_time| *active_time > 0.0)
+            .map(|active_time| active_time as u64)
+    }
+
     fn get_gaia_picture_file_name(&self) -> Option<String> {
         self.profile["gaia_picture_file_name"]
             .as_str()
@@ -450,4 +509,44 @@ pub struct ChromeProfilePreferences {
     pub profile_dir_name: String,
     pub name: String,
     pub avatar_file_path_relative_to_config: Option<PathBuf>,
+    pub account_email: Option<String>,
+    pub account_domain: Option<String>,
+    pub last_active_time: Option<u64>,
+    // the profile browser opens on start
+    pub is_last_used: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_last_used_and_active_time() {
+        let local_state = json!({
+            "profile": {
+                "last_used": "Profile 1",
+                "info_cache": {
+                    "Default": { "name": "Person 1", "active_time": 1700000000.25 },
+                    "Profile 1": { "name": "Work", "active_time": 1718271043.512 },
+                    "Profile 2": { "name": "Old" }
+                }
+            }
+        });
+
+        let profiles = ChromeInfoCacheMap::new_from_local_state_value(&local_state)
+            .parse_chrome_local_state_profiles();
+        let summary: Vec<(&str, Option<u64>, bool)> = profiles
+            .iter()
+            .map(|p| (p.profile_dir_name.as_str(), p.last_active_time, p.is_last_used))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Profile 2", None, false),
+                ("Default", Some(1700000000), false),
+                ("Profile 1", Some(1718271043), true),
+            ]
+        );
+    }
 }
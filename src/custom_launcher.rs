@@ -0,0 +1,98 @@
+use url::Url;
+
+use crate::launch_plan::{DetachStrategy, LaunchPlan};
+use crate::utils::CustomLauncherConfig;
+
+// used in configuration file to uniquely identify the launcher
+pub fn get_custom_launcher_unique_app_id(launcher: &CustomLauncherConfig) -> String {
+    return format!("custom-launcher:{}", launcher.name);
+}
+
+// launchers are run directly on every OS, also on macOS
+pub fn create_custom_launcher_plan(launcher: &CustomLauncherConfig, url: &str) -> LaunchPlan {
+    let arguments = expand_command_template(&launcher.command, url);
+    let (main_command, command_arguments) = arguments.split_at(1);
+    let main_command = main_command.first().unwrap(); // guaranteed to not be empty
+
+    let mut launch_plan = LaunchPlan::new(
+        main_command.as_str(),
+        command_arguments.to_vec(),
+        DetachStrategy::Spawn,
+    );
+    launch_plan.cwd = launcher.working_dir.clone();
+
+    return launch_plan;
+}
+
+// replaces {url}, {host} and {path} in every argument;
+// appends the url as the last argument if the template does not contain {url}
+fn expand_command_template(command_template: &[String], url_str: &str) -> Vec<String> {
+    let url_maybe = Url::parse(url_str).ok();
+    let host = url_maybe
+        .as_ref()
+        .and_then(|url| url.host_str())
+        .unwrap_or_default()
+        .to_string();
+    let path = url_maybe
+        .as_ref()
+        .map(|url| url.path().to_string())
+        .unwrap_or_default();
+
+    let has_url_placeholder = command_template.iter().any(|arg| arg.contains("{url}"));
+
+    let mut arguments: Vec<String> = command_template
+        .iter()
+        .map(|arg| {
+            arg.replace("{url}", url_str)
+                .replace("{host}", host.as_str())
+                .replace("{path}", path.as_str())
+        })
+        .collect();
+
+    if !has_url_placeholder {
+        arguments.push(url_str.to_string());
+    }
+
+    return arguments;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_strings(args: &[&str]) -> Vec<String> {
+        return args.iter().map(|arg| arg.to_string()).collect();
+    }
+
+    #[test]
+    fn test_expand_url_placeholder() {
+        let template = to_strings(&["ssh", "desktop", "xdg-open '{url}'"]);
+        assert_eq!(
+            expand_command_template(&template, "https://example.com/a?b=c"),
+            to_strings(&["ssh", "desktop", "xdg-open 'https://example.com/a?b=c'"])
+        );
+    }
+
+    #[test]
+    fn test_expand_host_and_path() {
+        let template = to_strings(&["open-repo.sh", "--host={host}", "{path}"]);
+        assert_eq!(
+            expand_command_template(&template, "https://github.com/org/repo"),
+            to_strings(&[
+                "open-repo.sh",
+                "--host=github.com",
+                "/org/repo",
+                "https://github.com/org/repo"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_url_appended_without_placeholder() {
+        let template = to_strings(&["/usr/bin/browser", "--new-window"]);
+        assert_eq!(
+            expand_command_template(&template, "https://example.com/"),
+            to_strings(&["/usr/bin/browser", "--new-window", "https://example.com/"])
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use url::Url;
+
+// Field codes of the Exec key in .desktop files,
+// see https://specifications.freedesktop.org/desktop-entry-spec/latest/exec-variables.html
+
+// values which the field codes are expanded to
+pub struct ExecFieldValues<'a> {
+    pub url: &'a str,
+    // `Icon` of the .desktop file
+    pub icon: Option<&'a str>,
+    // translated `Name` of the .desktop file
+    pub name: &'a str,
+    pub desktop_file_path: Option<&'a str>,
+}
+
+// %d, %D, %n, %N, %v and %m are deprecated and expand to nothing
+const DEPRECATED_FIELD_CODES: [char; 6] = ['d', 'D', 'n', 'N', 'v', 'm'];
+
+// true if the url is passed somewhere in the command, also inside a larger argument (e.g `--app=%u`)
+pub fn has_url_field_code(exec_args: &[String]) -> bool {
+    return exec_args.iter().any(|arg| {
+        find_field_codes(arg.as_str())
+            .iter()
+            .any(|field_code| matches!(field_code, 'u' | 'U' | 'f' | 'F'))
+    });
+}
+
+// ["firefox", "--name=%c", "%i", "%U"] -> ["firefox", "--name=Firefox", "--icon", "firefox", "https://example.com"]
+pub fn expand_field_codes(exec_args: &[String], values: &ExecFieldValues) -> Vec<String> {
+    return exec_args
+        .iter()
+        .flat_map(|arg| expand_arg(arg.as_str(), values))
+        .collect();
+}
+
+fn expand_arg(arg: &str, values: &ExecFieldValues) -> Vec<String> {
+    // the list and icon codes may only be used as whole arguments
+    match arg {
+        "%u" | "%U" => return vec![values.url.to_string()],
+        "%f" | "%F" => return vec![to_file_arg(values.url)],
+        "%i" => {
+            return values
+                .icon
+                .map(|icon| vec!["--icon".to_string(), icon.to_string()])
+                .unwrap_or_default();
+        }
+        // no argument at all if the location isn't known
+        "%k" => {
+            return values
+                .desktop_file_path
+                .map(|desktop_file_path| vec![desktop_file_path.to_string()])
+                .unwrap_or_default();
+        }
+        _ => {}
+    }
+
+    let mut expanded = String::new();
+    let mut only_removed_field_codes = !arg.is_empty();
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            only_removed_field_codes = false;
+            continue;
+        }
+
+        let field_code_maybe = chars.next();
+        match field_code_maybe {
+            Some('%') => expanded.push('%'),
+            Some('u') | Some('U') => expanded.push_str(values.url),
+            Some('f') | Some('F') => expanded.push_str(to_file_arg(values.url).as_str()),
+            Some('c') => expanded.push_str(values.name),
+            Some('k') => expanded.push_str(values.desktop_file_path.unwrap_or_default()),
+            Some(field_code)
+                if field_code == 'i' || DEPRECATED_FIELD_CODES.contains(&field_code) =>
+            {
+                continue;
+            }
+            // not a valid field code, kept as is
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+        only_removed_field_codes = false;
+    }
+
+    // e.g a deprecated "%d" argument is removed instead of passed as ""
+    if only_removed_field_codes {
+        return vec![];
+    }
+
+    return vec![expanded];
+}
+
+// field codes used in the argument, e.g "--app=%u" -> ['u']
+fn find_field_codes(arg: &str) -> Vec<char> {
+    let mut field_codes: Vec<char> = Vec::new();
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.next() {
+            Some('%') | None => {}
+            Some(field_code) => field_codes.push(field_code),
+        }
+    }
+
+    return field_codes;
+}
+
+// %f takes a local file path; other urls can't be downloaded here, so they are passed as is
+fn to_file_arg(url_str: &str) -> String {
+    let file_path_maybe = Url::parse(url_str)
+        .ok()
+        .filter(|url| url.scheme() == "file")
+        .and_then(|url| url.to_file_path().ok());
+
+    return match file_path_maybe {
+        Some(file_path) => file_path.display().to_string(),
+        None => url_str.to_string(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX_VALUES: ExecFieldValues = ExecFieldValues {
+        url: "https://example.com/?q=100%25",
+        icon: Some("firefox"),
+        name: "Firefox Web Browser",
+        desktop_file_path: Some("/usr/share/applications/firefox.desktop"),
+    };
+
+    fn expand(exec: &str, values: &ExecFieldValues) -> Vec<String> {
+        let exec_args = shell_words::split(exec).unwrap();
+        return expand_field_codes(&exec_args, values);
+    }
+
+    #[test]
+    fn test_expand_browser_exec_lines() {
+        assert_eq!(
+            expand("/usr/lib/firefox/firefox %u", &FIREFOX_VALUES),
+            vec!["/usr/lib/firefox/firefox", "https://example.com/?q=100%25"]
+        );
+        assert_eq!(
+            expand("/usr/bin/google-chrome-stable %U", &FIREFOX_VALUES),
+            vec![
+                "/usr/bin/google-chrome-stable",
+                "https://example.com/?q=100%25"
+            ]
+        );
+        assert_eq!(
+            expand("qutebrowser --untrusted-args %u", &FIREFOX_VALUES),
+            vec![
+                "qutebrowser",
+                "--untrusted-args",
+                "https://example.com/?q=100%25"
+            ]
+        );
+        assert_eq!(
+            expand(
+                "/usr/bin/flatpak run --branch=stable --arch=x86_64 --command=firefox --file-forwarding org.mozilla.firefox @@u %u @@",
+                &FIREFOX_VALUES
+            ),
+            vec![
+                "/usr/bin/flatpak",
+                "run",
+                "--branch=stable",
+                "--arch=x86_64",
+                "--command=firefox",
+                "--file-forwarding",
+                "org.mozilla.firefox",
+                "@@u",
+                "https://example.com/?q=100%25",
+                "@@"
+            ]
+        );
+        assert_eq!(
+            expand(
+                "env BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/firefox_firefox.desktop /snap/bin/firefox %u",
+                &FIREFOX_VALUES
+            ),
+            vec![
+                "env",
+                "BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/firefox_firefox.desktop",
+                "/snap/bin/firefox",
+                "https://example.com/?q=100%25"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_other_field_codes() {
+        // KDE apps pass the icon and caption
+        assert_eq!(
+            expand("konqueror -qwindowtitle %c %i %u", &FIREFOX_VALUES),
+            vec![
+                "konqueror",
+                "-qwindowtitle",
+                "Firefox Web Browser",
+                "--icon",
+                "firefox",
+                "https://example.com/?q=100%25"
+            ]
+        );
+        assert_eq!(
+            expand(
+                "browser --desktop-file=%k --app=%u --name=\"%c\"",
+                &FIREFOX_VALUES
+            ),
+            vec![
+                "browser",
+                "--desktop-file=/usr/share/applications/firefox.desktop",
+                "--app=https://example.com/?q=100%25",
+                "--name=Firefox Web Browser"
+            ]
+        );
+        assert_eq!(
+            expand(
+                "sh -c 'echo 100%% done' %k %i %U",
+                &ExecFieldValues {
+                    url: "https://example.com/",
+                    icon: None,
+                    name: "",
+                    desktop_file_path: None,
+                }
+            ),
+            vec!["sh", "-c", "echo 100% done", "https://example.com/"]
+        );
+    }
+
+    #[test]
+    fn test_expand_deprecated_and_file_field_codes() {
+        let values = ExecFieldValues {
+            url: "file:///home/user/page.html",
+            icon: None,
+            name: "Viewer",
+            desktop_file_path: None,
+        };
+        assert_eq!(
+            expand("viewer %d %D %n %N %v %m --dir=%d %F", &values),
+            vec!["viewer", "--dir=", "/home/user/page.html"]
+        );
+        assert_eq!(
+            expand("viewer %f", &FIREFOX_VALUES),
+            vec!["viewer", "https://example.com/?q=100%25"]
+        );
+        assert_eq!(expand("viewer %s", &values), vec!["viewer", "%s"]);
+    }
+
+    #[test]
+    fn test_has_url_field_code() {
+        let to_args = |exec: &str| shell_words::split(exec).unwrap();
+        assert!(has_url_field_code(&to_args("firefox %u")));
+        assert!(has_url_field_code(&to_args("viewer %F")));
+        assert!(has_url_field_code(&to_args("chromium --app=%U")));
+        assert!(!has_url_field_code(&to_args("spotify %i %c")));
+        assert!(!has_url_field_code(&to_args("sh -c 'echo 100%%u'")));
+    }
+}
@@ -1,19 +1,28 @@
 use std::collections::HashMap;
+use std::fs;
 use std::fs::File;
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
 use configparser::ini::{Ini, IniDefault};
 use naive_cityhash::cityhash64;
 use serde_json::Value;
 use tracing::{debug, info};
 
-use crate::InstalledBrowserProfile;
+use crate::{account_rules, InstalledBrowserProfile};
 
+// binary_path must be the actual binary Firefox runs as (symlinks resolved),
+// because the install dir hash is calculated from its directory, e.g
+//   macOS:   /Applications/Firefox.app/Contents/MacOS/firefox
+//   Windows: C:\Program Files\Mozilla Firefox\firefox.exe
+//   Linux:   /usr/lib/firefox/firefox
+//   snap:    /snap/firefox/current/usr/lib/firefox/firefox
+//   flatpak: /app/lib/firefox/firefox (path inside the sandbox)
 pub fn find_firefox_profiles(
     firefox_profiles_dir: &Path,
     binary_path: &Path,
-    _app_id: &str,
+    app_id: &str,
 ) -> Vec<InstalledBrowserProfile> {
     let binary_dir = binary_path.parent().and_then(|p| p.to_str()).unwrap_or("");
     // binary_dir is the path where binary is (without trailing slash)
@@ -52,6 +61,10 @@ pub fn find_firefox_profiles(
 
     // profile locked to an firefox instance
     let mut locked_profile_path_and_hash: HashMap<String, String> = HashMap::new();
+    // default profile of this install
+    let mut install_default_profile_path_maybe: Option<String> = None;
+    // hashes of all installs which have used this profiles.ini
+    let mut install_hashes: Vec<String> = Vec::new();
 
     // 1. find all default profiles,
     //    see https://support.mozilla.org/en-US/kb/understanding-depth-profile-installation
@@ -62,19 +75,10 @@ pub fn find_firefox_profiles(
         }
         // e.g "9F3C89D8F8FDBC89"
         let installation_dir_hashed = install_key.strip_prefix("Install").unwrap();
+        install_hashes.push(installation_dir_hashed.to_string());
         if !install_values.contains_key("Default") {
             continue;
         }
-        if !install_values.contains_key("Locked") {
-            continue;
-        }
-        // build a map of profile to installation hash?
-
-        /*if installation_dir_hashed != install_dir_hash {
-            // skip if not a rule for this browser
-            continue;
-        }*/
-
         // can be relative or absolute
         let default_profile_path = install_values
             .get("Default")
@@ -84,6 +88,14 @@ pub fn find_firefox_profiles(
             .unwrap()
             .to_string();
 
+        if installation_dir_hashed == install_dir_hash.as_str() {
+            install_default_profile_path_maybe = Some(default_profile_path.clone());
+        }
+
+        if !install_values.contains_key("Locked") {
+            continue;
+        }
+
         let default_locked = install_values
             .get("Locked")
             .as_ref()
@@ -99,6 +111,17 @@ pub fn find_firefox_profiles(
         }
     }
 
+    // if our hash is unknown, then it was probably calculated from the wrong binary
+    // (e.g a wrapper script like /usr/bin/firefox), so we can't tell which profiles are ours
+    // and hiding them would also hide the default profile of this install
+    let is_known_install = install_hashes.contains(&install_dir_hash);
+    if !is_known_install && !install_hashes.is_empty() {
+        info!(
+            "Install hash {} of {} is not in profiles.ini, showing profiles of all installs",
+            install_dir_hash, app_id
+        );
+    }
+
     let profiles_ini_map = profiles_ini_config.load(&profiles_ini_path).unwrap();
     for (_profile_key, profile_values) in profiles_ini_map.into_iter() {
         if !profile_values.contains_key("Path") {
@@ -113,18 +136,33 @@ pub fn find_firefox_profiles(
             .unwrap()
             .to_string();
 
+        // profiles.ini can be shared by many installs (e.g release and nightly),
+        // each install locks its own default profile
         let profile_hash_maybe = locked_profile_path_and_hash.get(profile_path.as_str());
-        if let Some(profile_hash) = profile_hash_maybe {
-            // if this profile has some other hash than current firefox, then skip the profile
-            if profile_hash.to_string() != install_dir_hash {
-                // `install_dir_hash`, that we calculate based on binary path,
-                // is not always correct, e.g in case of snap package in linux,
-                // so, let's not use it yet to exclude profiles.
-                // Users can always manually hide these profiles instead.
-                // TODO: https://github.com/Browsers-software/barowsers/issues/81
-                //continue;
+        let locked_to_other_install = match profile_hash_maybe {
+            Some(profile_hash) if profile_hash.as_str() == install_dir_hash.as_str() => {
+                info!(
+                    "Profile '{}' of {} is locked to this install ({})",
+                    profile_path, app_id, profile_hash
+                );
+                false
             }
-        }
+            Some(profile_hash) if is_known_install => {
+                info!(
+                    "Profile '{}' of {} is locked to other install ({}), this install is {}",
+                    profile_path, app_id, profile_hash, install_dir_hash
+                );
+                true
+            }
+            Some(_) => false,
+            None => {
+                info!(
+                    "Profile '{}' of {} is not locked to any install",
+                    profile_path, app_id
+                );
+                false
+            }
+        };
 
         let profile_dir = if !profile_path.starts_with("/") {
             firefox_profiles_dir.join(profile_path.as_str())
@@ -139,69 +177,193 @@ pub fn find_firefox_profiles(
             continue;
         }
 
-        let mut containers = Vec::new();
+        let name_maybe = profile_values
+            .get("Name")
+            .and_then(|a| a.as_ref())
+            .map(|b| b.to_string());
+
+        let profile_name = name_maybe.unwrap();
+
+        // installs before Firefox 67 share a single default profile, marked with Default=1
+        let is_browser_default = match install_default_profile_path_maybe.as_ref() {
+            Some(install_default_profile_path) => install_default_profile_path == &profile_path,
+            None => profile_values
+                .get("Default")
+                .and_then(|a| a.as_ref())
+                .is_some_and(|default| default == "1"),
+        };
+
+        browser_profiles.extend(profile_dir_to_profiles(
+            profile_dir.as_path(),
+            profile_name.as_str(),
+            profile_name.as_str(),
+            locked_to_other_install,
+            is_browser_default,
+            false,
+        ));
+    }
+
+    mark_ambiguous_profile_names(&mut browser_profiles);
+    return browser_profiles;
+}
+
+// Profiles kept outside of profiles.ini, e.g in project folders.
+// They are identified and launched by their directory, so their id does not depend on profiles.ini.
+pub fn find_standalone_firefox_profiles(
+    profile_dirs: &[(String, PathBuf)],
+) -> Vec<InstalledBrowserProfile> {
+    let mut browser_profiles: Vec<InstalledBrowserProfile> = Vec::new();
 
-        let mut open_url_in_container_extension_installed = false;
-        let extensions_json_file = profile_dir.join("extensions.json");
-        if !extensions_json_file.exists() {
+    for (name, profile_dir) in profile_dirs {
+        if !profile_dir.is_dir() {
             info!(
-                "Skipping containers for profile '{}', because it does not have extensions.json file",
+                "Skipping standalone profile directory '{}', because it does not exist",
                 profile_dir.display()
             );
-        } else {
-            open_url_in_container_extension_installed =
-                has_open_url_in_container_extension_installed(extensions_json_file.as_path());
+            continue;
         }
 
-        if open_url_in_container_extension_installed {
-            let containers_json_file = profile_dir.join("containers.json");
-            if !containers_json_file.exists() {
-                info!(
+        let profile_name = if name.is_empty() {
+            profile_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| profile_dir.display().to_string())
+        } else {
+            name.clone()
+        };
+        let profile_dir_str = profile_dir.to_string_lossy().to_string();
+
+        browser_profiles.extend(profile_dir_to_profiles(
+            profile_dir.as_path(),
+            profile_dir_str.as_str(),
+            profile_name.as_str(),
+            false,
+            false,
+            true,
+        ));
+    }
+
+    return browser_profiles;
+}
+
+// the profile itself and its containers
+fn profile_dir_to_profiles(
+    profile_dir: &Path,
+    profile_cli_arg_value: &str,
+    profile_name: &str,
+    locked_to_other_install: bool,
+    is_browser_default: bool,
+    launch_by_dir: bool,
+) -> Vec<InstalledBrowserProfile> {
+    let mut browser_profiles: Vec<InstalledBrowserProfile> = Vec::new();
+    let mut containers = Vec::new();
+
+    let mut open_url_in_container_extension_installed = false;
+    let extensions_json_file = profile_dir.join("extensions.json");
+    if !extensions_json_file.exists() {
+        info!(
+            "Skipping containers for profile '{}', because it does not have extensions.json file",
+            profile_dir.display()
+        );
+    } else {
+        open_url_in_container_extension_installed =
+            has_open_url_in_container_extension_installed(extensions_json_file.as_path());
+    }
+
+    if open_url_in_container_extension_installed {
+        let containers_json_file = profile_dir.join("containers.json");
+        if !containers_json_file.exists() {
+            info!(
                 "Skipping containers for profile '{}', because it does not have containers.json file",
                 profile_dir.display()
             );
-            } else {
-                // containers for this profile
-                info!(
-                    "Checking containers from {}",
-                    containers_json_file.as_path().to_str().unwrap()
-                );
-                containers = containers_json_map(containers_json_file.as_path());
-            }
+        } else {
+            // containers for this profile
+            info!(
+                "Checking containers from {}",
+                containers_json_file.as_path().to_str().unwrap()
+            );
+            containers = containers_json_map(containers_json_file.as_path());
         }
+    }
 
-        let name_maybe = profile_values
-            .get("Name")
-            .and_then(|a| a.as_ref())
-            .map(|b| b.to_string());
-
-        let profile_name = name_maybe.unwrap();
-
-        // Even if profile has containers, also add a non-container option
-        browser_profiles.push(InstalledBrowserProfile {
-            profile_cli_arg_value: profile_name.to_string(),
-            profile_cli_container_name: None,
-            profile_name: profile_name.to_string(),
-            profile_icon: None,
-            profile_restricted_url_patterns: vec![],
-        });
-
-        if !containers.is_empty() {
-            for container in containers {
-                browser_profiles.push(InstalledBrowserProfile {
-                    profile_cli_arg_value: profile_name.to_string(),
-                    profile_cli_container_name: Some(container.id.to_string()),
-                    profile_name: profile_name.to_string() + " " + container.name.as_str(),
-                    profile_icon: None,
-                    profile_restricted_url_patterns: vec![],
-                })
-            }
+    let last_used_maybe = read_last_used_time(profile_dir);
+    let profile_dir_str = profile_dir.to_string_lossy().to_string();
+
+    let account_email_maybe = read_signed_in_user_email(profile_dir);
+    let account_domain_maybe = account_email_maybe
+        .as_ref()
+        .and_then(|email| account_rules::organization_domain_from_email(email));
+
+    // Even if profile has containers, also add a non-container option
+    browser_profiles.push(InstalledBrowserProfile {
+        profile_cli_arg_value: profile_cli_arg_value.to_string(),
+        profile_cli_container_name: None,
+        profile_name: profile_name.to_string(),
+        profile_icon: None,
+        profile_restricted_url_patterns: vec![],
+        locked_to_other_install: locked_to_other_install,
+        profile_container_color: None,
+        profile_container_icon: None,
+        profile_account_email: account_email_maybe,
+        profile_account_domain: account_domain_maybe,
+        profile_last_used: last_used_maybe,
+        profile_is_browser_default: is_browser_default,
+        profile_dir: Some(profile_dir_str.clone()),
+        profile_launch_by_dir: launch_by_dir,
+        profile_is_temporary: false,
+    });
+
+    if !containers.is_empty() {
+        for container in containers {
+            browser_profiles.push(InstalledBrowserProfile {
+                profile_cli_arg_value: profile_cli_arg_value.to_string(),
+                profile_cli_container_name: Some(container.id.to_string()),
+                profile_name: profile_name.to_string() + " " + container.name.as_str(),
+                profile_icon: None,
+                profile_restricted_url_patterns: vec![],
+                locked_to_other_install: locked_to_other_install,
+                profile_container_color: Some(container.color.to_string()),
+                profile_container_icon: Some(container.icon.to_string()),
+                profile_account_email: None,
+                profile_account_domain: None,
+                profile_last_used: last_used_maybe,
+                profile_is_browser_default: false,
+                profile_dir: Some(profile_dir_str.clone()),
+                profile_launch_by_dir: launch_by_dir,
+                profile_is_temporary: false,
+            })
         }
     }
 
     return browser_profiles;
 }
 
+// `-P <name>` picks the first profile with that name,
+// so profiles sharing a name are launched with `--profile <dir>` instead
+fn mark_ambiguous_profile_names(browser_profiles: &mut Vec<InstalledBrowserProfile>) {
+    let ambiguous_names: Vec<String> = browser_profiles
+        .iter()
+        .filter(|profile| {
+            browser_profiles.iter().any(|other| {
+                other.profile_cli_arg_value == profile.profile_cli_arg_value
+                    && other.profile_dir != profile.profile_dir
+            })
+        })
+        .map(|profile| profile.profile_cli_arg_value.clone())
+        .collect();
+
+    for profile in browser_profiles.iter_mut() {
+        if ambiguous_names.contains(&profile.profile_cli_arg_value) {
+            info!(
+                "Profile name '{}' is used by several profiles, launching it by directory",
+                profile.profile_cli_arg_value
+            );
+            profile.profile_launch_by_dir = true;
+        }
+    }
+}
+
 // has "open-url-in-container" extension installed, which adds "ext+container" protocol support
 fn has_open_url_in_container_extension_installed(extensions_json_file_path: &Path) -> bool {
     let extension_id = "{f069aec0-43c5-4bbf-b6b4-df95c4326b98}";
@@ -226,6 +388,30 @@ fn has_open_url_in_container_extension_installed(extensions_json_file_path: &Pat
     return false;
 }
 
+// email of the Firefox Account the profile is signed in to (Firefox Sync)
+fn read_signed_in_user_email(profile_dir: &Path) -> Option<String> {
+    let signed_in_user_json_file = profile_dir.join("signedInUser.json");
+    let file = File::open(signed_in_user_json_file.as_path()).ok()?;
+    let reader = BufReader::new(file);
+    let v: Value = serde_json::from_reader(reader).ok()?;
+    return v["accountData"]["email"]
+        .as_str()
+        .filter(|email| !email.is_empty())
+        .map(|email| email.to_string());
+}
+
+// Firefox does not record profile usage, so this is only a heuristic: prefs.js is written
+// when the profile is closed, but also whenever a preference changes while it is running
+// (e.g. by an update or an extension), which makes such a profile look recently used.
+// Only compared with other profiles of the same Firefox install (see sort_browser_profiles).
+fn read_last_used_time(profile_dir: &Path) -> Option<u64> {
+    let modified = fs::metadata(profile_dir.join("prefs.js"))
+        .and_then(|metadata| metadata.modified())
+        .ok()?;
+    let since_epoch = modified.duration_since(UNIX_EPOCH).ok()?;
+    return Some(since_epoch.as_secs());
+}
+
 fn containers_json_map(containers_json_file_path: &Path) -> Vec<FirefoxContainer> {
     // Open the file in read-only mode with buffer.
     let file = File::open(containers_json_file_path).unwrap();
@@ -262,9 +448,16 @@ fn containers_json_map(containers_json_file_path: &Path) -> Vec<FirefoxContainer
                 identity["name"].as_str().unwrap_or("Unknown")
             };
 
+            // e.g "blue", "turquoise", "toolbar"
+            let color = identity["color"].as_str().unwrap_or("toolbar");
+            // e.g "fingerprint", "briefcase", "cart"
+            let icon = identity["icon"].as_str().unwrap_or("circle");
+
             let container = FirefoxContainer {
                 id: name.to_string(),
                 name: name.to_string(),
+                color: color.to_string(),
+                icon: icon.to_string(),
             };
             containers.push(container);
         }
@@ -276,6 +469,8 @@ struct FirefoxContainer {
     id: String,
     // aka cookie store id in Firefox
     name: String,
+    color: String,
+    icon: String,
 }
 
 fn hash_firefox_install_dir(ff_binary_dir: &str) -> String {
@@ -294,3 +489,141 @@ fn hash_firefox_install_dir(ff_binary_dir: &str) -> String {
     let hash_u64_str = format!("{:X}", hash_u64);
     return hash_u64_str;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_dir;
+
+    #[test]
+    fn test_find_profiles_with_same_name() {
+        let firefox_dir = create_test_dir("firefox", "same-name");
+        let other_dir = create_test_dir("firefox", "same-name-other");
+        fs::create_dir_all(firefox_dir.join("Profiles/a1.default")).unwrap();
+        fs::create_dir_all(firefox_dir.join("Profiles/b2.work")).unwrap();
+        fs::write(
+            firefox_dir.join("profiles.ini"),
+            format!(
+                "[Profile0]\nName=default\nIsRelative=1\nPath=Profiles/a1.default\nDefault=1\n\n\
+                 [Profile1]\nName=work\nIsRelative=1\nPath=Profiles/b2.work\n\n\
+                 [Profile2]\nName=work\nIsRelative=0\nPath={}\n\n\
+                 [General]\nStartWithLastProfile=1\nVersion=2\n",
+                other_dir.display()
+            ),
+        )
+        .unwrap();
+
+        let mut profiles = find_firefox_profiles(
+            firefox_dir.as_path(),
+            Path::new("/usr/lib/firefox/firefox"),
+            "firefox",
+        );
+        profiles.sort_by_key(|p| p.profile_dir.clone());
+
+        let default_profile = profiles
+            .iter()
+            .find(|p| p.profile_cli_arg_value == "default")
+            .unwrap();
+        assert!(default_profile.profile_is_browser_default);
+        assert!(!default_profile.profile_launch_by_dir);
+        assert_eq!(
+            default_profile.profile_dir,
+            Some(
+                firefox_dir
+                    .join("Profiles/a1.default")
+                    .to_string_lossy()
+                    .to_string()
+            )
+        );
+
+        let work_profiles: Vec<&InstalledBrowserProfile> = profiles
+            .iter()
+            .filter(|p| p.profile_cli_arg_value == "work")
+            .collect();
+        assert_eq!(work_profiles.len(), 2);
+        assert!(work_profiles.iter().all(|p| p.profile_launch_by_dir));
+        assert!(work_profiles.iter().all(|p| !p.profile_is_browser_default));
+    }
+
+    #[test]
+    fn test_hash_firefox_install_dir() {
+        // as seen in [Install...] sections of profiles.ini
+        assert_eq!(
+            hash_firefox_install_dir("/Applications/Firefox.app/Contents/MacOS"),
+            "2656FF1E876E9973"
+        );
+        assert_eq!(
+            hash_firefox_install_dir(r"C:\Program Files\Mozilla Firefox"),
+            "308046B0AF4A39CB"
+        );
+        assert_eq!(hash_firefox_install_dir("/usr/lib/firefox"), "4F96D1932A9F858E");
+    }
+
+    fn find_locked_to_other_install(firefox_dir: &Path, binary_path: &str) -> Vec<(String, bool)> {
+        let mut profiles: Vec<(String, bool)> =
+            find_firefox_profiles(firefox_dir, Path::new(binary_path), "firefox")
+                .into_iter()
+                .map(|p| (p.profile_name, p.locked_to_other_install))
+                .collect();
+        profiles.sort();
+        return profiles;
+    }
+
+    #[test]
+    fn test_profiles_locked_to_other_install() {
+        let firefox_dir = create_test_dir("firefox", "locked");
+        fs::create_dir_all(firefox_dir.join("Profiles/a1.default-release")).unwrap();
+        fs::create_dir_all(firefox_dir.join("Profiles/b2.default-nightly")).unwrap();
+        fs::create_dir_all(firefox_dir.join("Profiles/c3.work")).unwrap();
+        fs::write(
+            firefox_dir.join("profiles.ini"),
+            "[Install4F96D1932A9F858E]\nDefault=Profiles/a1.default-release\nLocked=1\n\n\
+             [Install11457493C5A56847]\nDefault=Profiles/b2.default-nightly\nLocked=1\n\n\
+             [Profile0]\nName=default-release\nIsRelative=1\nPath=Profiles/a1.default-release\n\n\
+             [Profile1]\nName=default-nightly\nIsRelative=1\nPath=Profiles/b2.default-nightly\n\n\
+             [Profile2]\nName=work\nIsRelative=1\nPath=Profiles/c3.work\n\n\
+             [General]\nStartWithLastProfile=1\nVersion=2\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_locked_to_other_install(firefox_dir.as_path(), "/usr/lib/firefox/firefox"),
+            vec![
+                ("default-nightly".to_string(), true),
+                ("default-release".to_string(), false),
+                ("work".to_string(), false),
+            ]
+        );
+
+        // hash of a wrapper script dir matches no install, so nothing is hidden
+        assert_eq!(
+            find_locked_to_other_install(firefox_dir.as_path(), "/usr/bin/firefox"),
+            vec![
+                ("default-nightly".to_string(), false),
+                ("default-release".to_string(), false),
+                ("work".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_standalone_profiles() {
+        let project_dir = create_test_dir("firefox", "standalone");
+        let profile_dir = project_dir.join("ff-profile");
+        fs::create_dir_all(profile_dir.as_path()).unwrap();
+
+        let profiles = find_standalone_firefox_profiles(&[
+            ("Project X".to_string(), profile_dir.clone()),
+            ("".to_string(), profile_dir.clone()),
+            ("Missing".to_string(), project_dir.join("missing")),
+        ]);
+        let names: Vec<&str> = profiles.iter().map(|p| p.profile_name.as_str()).collect();
+        assert_eq!(names, vec!["Project X", "ff-profile"]);
+
+        let profile_dir_str = profile_dir.to_string_lossy().to_string();
+        assert_eq!(profiles[0].profile_cli_arg_value, profile_dir_str);
+        assert_eq!(profiles[0].profile_dir, Some(profile_dir_str));
+        assert!(profiles[0].profile_launch_by_dir);
+        assert!(!profiles[0].profile_is_browser_default);
+    }
+}
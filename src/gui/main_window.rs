@@ -4,7 +4,7 @@ use std::sync::Arc;
 use druid::piet::InterpolationMode;
 use druid::widget::{
     Container, Controller, ControllerHost, CrossAxisAlignment, Either, Flex, Image, Label,
-    LineBreaking, List, ZStack,
+    LineBreaking, List, Painter, SizedBox, ZStack,
 };
 use druid::{
     Color, Env, Event, EventCtx, FontDescriptor, FontFamily, FontWeight, ImageBuf, Lens, LensExt,
@@ -47,6 +47,9 @@ pub const RESTORE_HIDDEN_PROFILE: Selector<String> =
 
 pub const MOVE_PROFILE: Selector<(String, MoveTo)> = Selector::new("browsers.move_profile");
 
+// copies the command which would open the link in the profile (browser profile index sent via command)
+pub const COPY_LAUNCH_COMMAND: Selector<usize> = Selector::new("browsers.copy_launch_command");
+
 const WINDOW_BORDER_WIDTH: f64 = 1.0;
 const PADDING_X: f64 = 5.0;
 const PADDING_Y: f64 = 10.0;
@@ -59,8 +62,8 @@ impl FocusData for UIState {
     }
 }
 
-// need to implement this for the Widget<((bool, UISettings), UIBrowser)> types we declared
-impl FocusData for ((bool, UISettings), UIBrowser) {
+// need to implement this for the Widget<(((bool, bool), UISettings), UIBrowser)> types we declared
+impl FocusData for (((bool, bool), UISettings), UIBrowser) {
     fn has_autofocus(&self) -> bool {
         let browser = &self.1;
         return browser.filtered_index == 0;
@@ -193,7 +196,10 @@ impl MainWindow {
         let browsers_list = List::new(move || create_browser(ImageBuf::empty(), ImageBuf::empty()))
             .with_spacing(0.0)
             .lens((
-                (UIState::incognito_mode, UIState::ui_settings),
+                (
+                    (UIState::incognito_mode, UIState::app_window_mode),
+                    UIState::ui_settings,
+                ),
                 UIState::filtered_browsers,
             ))
             .scroll();
@@ -322,13 +328,20 @@ pub(crate) fn calculate_window_position(
     return Point::new(x, y);
 }
 
-fn create_browser_label() -> Label<((bool, UISettings), UIBrowser)> {
+fn create_browser_label() -> Label<(((bool, bool), UISettings), UIBrowser)> {
     let browser_label = Label::dynamic(
-        |((incognito_mode, _), item): &((bool, UISettings), UIBrowser), _env| {
+        |(((incognito_mode, app_window_mode), _), item): &(
+            ((bool, bool), UISettings),
+            UIBrowser,
+        ),
+         _env| {
             let mut name = item.browser_name.clone();
             if item.supports_incognito && *incognito_mode {
                 name += " 👓";
             }
+            if item.supports_app_window && *app_window_mode {
+                name += " 🗔";
+            }
             name
         },
     )
@@ -340,10 +353,64 @@ fn create_browser_label() -> Label<((bool, UISettings), UIBrowser)> {
     browser_label
 }
 
+// symbol for Firefox container icon, drawn on top of the container color
+fn container_icon_symbol(icon_name: &str) -> &'static str {
+    return match icon_name {
+        "fingerprint" => "☝",
+        "briefcase" => "💼",
+        "dollar" => "$",
+        "cart" => "🛒",
+        "vacation" => "✈",
+        "gift" => "🎁",
+        "food" => "🍴",
+        "fruit" => "🍎",
+        "pet" => "🐾",
+        "tree" => "🌲",
+        "chill" => "🍸",
+        "fence" => "▦",
+        _ => "",
+    };
+}
+
+fn create_container_badge() -> impl Widget<(((bool, bool), UISettings), UIBrowser)> {
+    const BADGE_SIZE: f64 = 14.0;
+
+    let badge_background =
+        Painter::new(|ctx, (_, item): &(((bool, bool), UISettings), UIBrowser), env| {
+            let color_name = item.container_color.as_deref().unwrap_or_default();
+            let color = MainWindowTheme::container_color(color_name, env);
+            let border_color = env.get(MainWindowTheme::ENV_CONTAINER_BADGE_BORDER_COLOR);
+
+            let rounded_rect = ctx.size().to_rounded_rect(BADGE_SIZE / 2.0);
+            ctx.fill(rounded_rect, &color);
+            ctx.stroke(rounded_rect, &border_color, 1.0);
+        });
+
+    let badge_label =
+        Label::dynamic(|(_, item): &(((bool, bool), UISettings), UIBrowser), _env: &_| {
+            let icon_name = item.container_icon.as_deref().unwrap_or_default();
+            container_icon_symbol(icon_name).to_string()
+        })
+        .with_text_size(8.0)
+        .with_text_color(MainWindowTheme::ENV_CONTAINER_BADGE_TEXT_COLOR)
+        .with_text_alignment(TextAlignment::Center);
+
+    let badge = badge_label
+        .center()
+        .fix_size(BADGE_SIZE, BADGE_SIZE)
+        .background(badge_background);
+
+    return Either::new(
+        |(_, item): &(((bool, bool), UISettings), UIBrowser), _env| item.container_color.is_some(),
+        badge,
+        SizedBox::empty(),
+    );
+}
+
 fn create_browser(
     app_icon_buf: ImageBuf,
     profile_img_buf: ImageBuf,
-) -> impl Widget<((bool, UISettings), UIBrowser)> {
+) -> impl Widget<(((bool, bool), UISettings), UIBrowser)> {
     let icon_size = get_icon_size();
     let icon_padding = get_icon_padding();
 
@@ -370,10 +437,10 @@ fn create_browser(
         .lens(BrowserLens.then(UIBrowser::profile_icon_path));
 
     let item_label = Either::new(
-        |(_, item): &((bool, UISettings), UIBrowser), _env| item.supports_profiles,
+        |(_, item): &(((bool, bool), UISettings), UIBrowser), _env| item.supports_profiles,
         {
             let profile_label =
-                Label::dynamic(|(_, item): &((bool, UISettings), UIBrowser), _env: &_| {
+                Label::dynamic(|(_, item): &(((bool, bool), UISettings), UIBrowser), _env: &_| {
                     item.profile_name.clone()
                 })
                 .with_text_size(MainWindowTheme::ENV_PROFILE_LABEL_SIZE)
@@ -381,9 +448,23 @@ fn create_browser(
                 .with_text_alignment(TextAlignment::Start)
                 .with_text_color(MainWindowTheme::ENV_PROFILE_LABEL_COLOR);
 
+            // signed-in account as a subtitle, e.g "Work · you@acme.com"
+            let account_label =
+                Label::dynamic(|(_, item): &(((bool, bool), UISettings), UIBrowser), _env: &_| {
+                    item.account_email
+                        .as_ref()
+                        .map(|account_email| format!(" · {}", account_email))
+                        .unwrap_or_default()
+                })
+                .with_text_size(MainWindowTheme::ENV_PROFILE_LABEL_SIZE)
+                .with_line_break_mode(LineBreaking::Clip)
+                .with_text_alignment(TextAlignment::Start)
+                .with_text_color(MainWindowTheme::ENV_PROFILE_LABEL_COLOR);
+
             let profile_row = Flex::row()
                 //.with_child(profile_icon)
-                .with_child(profile_label);
+                .with_child(profile_label)
+                .with_flex_child(account_label, 1.0);
 
             Flex::column()
                 .cross_axis_alignment(CrossAxisAlignment::Fill)
@@ -397,13 +478,21 @@ fn create_browser(
         },
     );
 
-    let icon_stack = ZStack::new(image_widget).with_child(
-        profile_icon,
-        Vec2::new(1.0, 1.0),
-        Vec2::new(16.0, 16.0),
-        UnitPoint::new(0.1, 0.1),
-        Vec2::ZERO,
-    );
+    let icon_stack = ZStack::new(image_widget)
+        .with_child(
+            profile_icon,
+            Vec2::new(1.0, 1.0),
+            Vec2::new(16.0, 16.0),
+            UnitPoint::new(0.1, 0.1),
+            Vec2::ZERO,
+        )
+        .with_child(
+            create_container_badge(),
+            Vec2::ZERO,
+            Vec2::new(14.0, 14.0),
+            UnitPoint::new(0.9, 0.9),
+            Vec2::ZERO,
+        );
 
     let text_size = 10.0;
     let font = FontDescriptor::new(FontFamily::MONOSPACE)
@@ -411,12 +500,12 @@ fn create_browser(
         .with_size(text_size);
 
     let hotkey_label = Either::new(
-        move |((_, ui_settings), item): &((bool, UISettings), UIBrowser), _env| {
+        move |((_, ui_settings), item): &(((bool, bool), UISettings), UIBrowser), _env| {
             ui_settings.visual_settings.show_hotkeys && item.filtered_index < 9
         },
         {
             let hotkey_label =
-                Label::dynamic(|(_, item): &((bool, UISettings), UIBrowser), _env: &_| {
+                Label::dynamic(|(_, item): &(((bool, bool), UISettings), UIBrowser), _env: &_| {
                     let hotkey_number = item.filtered_index + 1;
                     let hotkey = hotkey_number.to_string();
                     hotkey
@@ -446,7 +535,7 @@ fn create_browser(
     let container = Container::new(icon_and_label)
         .fix_size(ITEM_WIDTH, ITEM_HEIGHT)
         .on_click(
-            move |_ctx, (_, data): &mut ((bool, UISettings), UIBrowser), _env| {
+            move |_ctx, (_, data): &mut (((bool, bool), UISettings), UIBrowser), _env| {
                 _ctx.get_external_handle()
                     .submit_command(
                         OPEN_LINK_IN_BROWSER,
@@ -459,13 +548,13 @@ fn create_browser(
 
     let container = FocusWidget::new(
         container,
-        |ctx, _: &((bool, UISettings), UIBrowser), _env| {
+        |ctx, _: &(((bool, bool), UISettings), UIBrowser), _env| {
             let size = ctx.size();
             let rounded_rect = size.to_rounded_rect(5.0);
             let color = Color::rgba(1.0, 1.0, 1.0, 0.25);
             ctx.fill(rounded_rect, &color);
         },
-        |ctx, (_, data): &((bool, UISettings), UIBrowser), _env| {
+        |ctx, (_, data): &(((bool, bool), UISettings), UIBrowser), _env| {
             if ctx.has_focus() {
                 ctx.get_external_handle()
                     .submit_command(
@@ -492,15 +581,15 @@ fn create_browser(
 
 struct ContextMenuController;
 
-impl<W: Widget<((bool, UISettings), UIBrowser)>> Controller<((bool, UISettings), UIBrowser), W>
-    for ContextMenuController
+impl<W: Widget<(((bool, bool), UISettings), UIBrowser)>>
+    Controller<(((bool, bool), UISettings), UIBrowser), W> for ContextMenuController
 {
     fn event(
         &mut self,
         child: &mut W,
         ctx: &mut EventCtx,
         event: &Event,
-        data: &mut ((bool, UISettings), UIBrowser),
+        data: &mut (((bool, bool), UISettings), UIBrowser),
         env: &Env,
     ) {
         match event {
@@ -595,16 +684,21 @@ fn make_context_menu(browser: &UIBrowser) -> Menu<UIState> {
         menu = menu.entry(MenuItem::new(hide_app_label).command(hide_all_profiles_command));
     }
 
+    let copy_launch_command = COPY_LAUNCH_COMMAND.with(browser.browser_profile_index);
+    menu = menu.entry(
+        MenuItem::new(LocalizedString::new("copy-launch-command")).command(copy_launch_command),
+    );
+
     menu
 }
 
-/* Extracts browser from the ((bool, UISettings), UIBrowser) tuple*/
+/* Extracts browser from the (((bool, bool), UISettings), UIBrowser) tuple*/
 struct BrowserLens;
 
-impl Lens<((bool, UISettings), UIBrowser), UIBrowser> for BrowserLens {
+impl Lens<(((bool, bool), UISettings), UIBrowser), UIBrowser> for BrowserLens {
     fn with<R, F: FnOnce(&UIBrowser) -> R>(
         &self,
-        data: &((bool, UISettings), UIBrowser),
+        data: &(((bool, bool), UISettings), UIBrowser),
         f: F,
     ) -> R {
         f(&data.1)
@@ -612,7 +706,7 @@ impl Lens<((bool, UISettings), UIBrowser), UIBrowser> for BrowserLens {
 
     fn with_mut<R, F: FnOnce(&mut UIBrowser) -> R>(
         &self,
-        data: &mut ((bool, UISettings), UIBrowser),
+        data: &mut (((bool, bool), UISettings), UIBrowser),
         f: F,
     ) -> R {
         f(&mut data.1)
@@ -9,7 +9,7 @@ use crate::gui::ui::{
     UIBehavioralSettings, UISettings, UIState, UIVisualSettings, SAVE_BEHAVIORAL_SETTINGS,
     SAVE_UI_SETTINGS,
 };
-use crate::utils::ConfiguredTheme;
+use crate::utils::{ConfiguredTheme, ProfileSortMode};
 
 pub(crate) fn general_content() -> impl Widget<UIState> {
     const TEXT_SIZE: f64 = 13.0;
@@ -54,6 +54,27 @@ pub(crate) fn general_content() -> impl Widget<UIState> {
         .with_flex_spacer(1.0)
         .with_child(hotkeys_switch);
 
+    let profile_sort_radio_group = ControllerHost::new(
+        RadioGroup::column(vec![
+            ("Alphabetical", ProfileSortMode::Alphabetical),
+            ("Most recently used", ProfileSortMode::MostRecentlyUsed),
+            ("Browser's default first", ProfileSortMode::BrowserDefaultFirst),
+        ]),
+        rules_view::SubmitCommandOnDataChange {
+            command: save_command.clone(),
+        },
+    )
+    .lens(
+        UIState::ui_settings
+            .then(UISettings::visual_settings)
+            .then(UIVisualSettings::profile_sort),
+    );
+
+    let profile_sort_radio_row = Flex::row()
+        .with_child(Label::new("Sort profiles").with_text_size(TEXT_SIZE))
+        .with_flex_spacer(1.0)
+        .with_child(profile_sort_radio_group);
+
     let label = Label::new("Restore App...").with_text_size(TEXT_SIZE);
 
     let restore_app_button =
@@ -114,6 +135,64 @@ pub(crate) fn general_content() -> impl Widget<UIState> {
         .with_child(unwrap_urls_switch);
     col = col.with_child(unwrap_urls_row).with_default_spacer();
 
+    let canonicalize_amp_urls_switch = ControllerHost::new(
+        Switch::new(),
+        rules_view::SubmitCommandOnDataChange {
+            command: SAVE_BEHAVIORAL_SETTINGS.with(()),
+        },
+    )
+    .lens(
+        UIState::ui_settings
+            .then(UISettings::behavioral_settings)
+            .then(UIBehavioralSettings::canonicalize_amp_urls),
+    );
+
+    let canonicalize_amp_urls_row = Flex::row()
+        .with_child(Label::new("Open AMP links as original page").with_text_size(TEXT_SIZE))
+        .with_flex_spacer(1.0)
+        .with_child(canonicalize_amp_urls_switch);
+    col = col
+        .with_child(canonicalize_amp_urls_row)
+        .with_default_spacer();
+
+    let canonicalize_mobile_urls_switch = ControllerHost::new(
+        Switch::new(),
+        rules_view::SubmitCommandOnDataChange {
+            command: SAVE_BEHAVIORAL_SETTINGS.with(()),
+        },
+    )
+    .lens(
+        UIState::ui_settings
+            .then(UISettings::behavioral_settings)
+            .then(UIBehavioralSettings::canonicalize_mobile_urls),
+    );
+
+    let canonicalize_mobile_urls_row = Flex::row()
+        .with_child(Label::new("Open mobile links as desktop page").with_text_size(TEXT_SIZE))
+        .with_flex_spacer(1.0)
+        .with_child(canonicalize_mobile_urls_switch);
+    col = col
+        .with_child(canonicalize_mobile_urls_row)
+        .with_default_spacer();
+
+    let temporary_profiles_switch = ControllerHost::new(
+        Switch::new(),
+        rules_view::SubmitCommandOnDataChange {
+            command: SAVE_BEHAVIORAL_SETTINGS.with(()),
+        },
+    )
+    .lens(
+        UIState::ui_settings
+            .then(UISettings::behavioral_settings)
+            .then(UIBehavioralSettings::temporary_profiles_enabled),
+    );
+
+    let temporary_profiles_row = Flex::row()
+        .with_child(Label::new("Offer temporary profiles").with_text_size(TEXT_SIZE))
+        .with_flex_spacer(1.0)
+        .with_child(temporary_profiles_switch);
+    col = col.with_child(temporary_profiles_row).with_default_spacer();
+
     let tooltip = Label::new(
         "To hide and move applications/profiles, close settings and just right-click on the application in the main dialog"
     )
@@ -123,6 +202,8 @@ pub(crate) fn general_content() -> impl Widget<UIState> {
     return col
         .with_child(Label::new("Applications and Profiles"))
         .with_default_spacer()
+        .with_child(profile_sort_radio_row)
+        .with_default_spacer()
         .with_child(restore_app_button)
         .with_default_spacer()
         .with_child(tooltip);
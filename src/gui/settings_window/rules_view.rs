@@ -5,13 +5,15 @@ use druid::lens::Identity;
 use druid::menu::MenuEventCtx;
 use druid::widget::{
     Button, Checkbox, Container, Controller, ControllerHost, CrossAxisAlignment, Either, EnvScope,
-    Flex, Label, LineBreaking, List, Maybe, TextBox,
+    Flex, Label, LineBreaking, List, Maybe, TextBox, ViewSwitcher,
 };
 use druid::{
     Color, Command, Data, Env, EventCtx, FontDescriptor, FontFamily, Key, LensExt, LifeCycle,
     LifeCycleCtx, Menu, MenuItem, Point, UpdateCtx, Widget, WidgetExt,
 };
 
+use crate::account_rules;
+use crate::account_rules::ProfileAccount;
 use crate::gui::ui::{
     SAVE_DEFAULT_RULE, SAVE_RULE, SAVE_RULES, UIBrowser, UIProfileAndIncognito, UISettings,
     UISettingsRule, UIState,
@@ -75,6 +77,7 @@ See https://github.com/Browsers-software/browsers/wiki/Rules for all the details
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_child(default_app(&browsers_arc2))
         .with_default_spacer()
+        .with_child(suggested_rules(&browsers))
         .with_flex_child(rules_list, 1.0)
         .with_child(add_rule_button)
         .expand_height();
@@ -166,6 +169,44 @@ fn create_incognito_checkbox(
     );
 }
 
+fn create_app_window_checkbox(
+    browsers: &Arc<Vec<UIBrowser>>,
+    command: Command,
+) -> impl Widget<Option<UIProfileAndIncognito>> + use<> {
+    let browsers_clone3 = browsers.clone();
+
+    return Maybe::new(
+        move || {
+            let browsers_clone4 = browsers_clone3.clone();
+            let command1 = command.clone();
+
+            let app_window_either = Either::new(
+                move |data: &UIProfileAndIncognito, _env| {
+                    let browser_maybe = find_browser(&browsers_clone4, data.profile.clone());
+                    browser_maybe
+                        .map(|p| p.supports_app_window)
+                        .unwrap_or(false)
+                },
+                {
+                    let app_window_checkbox = ControllerHost::new(
+                        Checkbox::from_label(Label::new("As App Window").with_font(FONT)),
+                        SubmitCommandOnDataChange {
+                            command: command1.clone(),
+                        },
+                    )
+                    .lens(UIProfileAndIncognito::app_window)
+                    .padding((10.0, 0.0, 0.0, 0.0));
+                    app_window_checkbox
+                },
+                Flex::column(),
+            );
+
+            app_window_either
+        },
+        || Flex::column(),
+    );
+}
+
 fn create_profile_label() -> Label<Option<UIProfileAndIncognito>> {
     let profile_label = Label::dynamic(|opener, _env| match opener {
         None => "Show".to_string(),
@@ -185,10 +226,14 @@ fn default_app(browsers: &Arc<Vec<UIBrowser>>) -> impl Widget<UISettings> + use<
     let incognito_save_command = SAVE_DEFAULT_RULE.with(());
     let incognito_maybe = create_incognito_checkbox(browsers, incognito_save_command);
 
+    let app_window_save_command = SAVE_DEFAULT_RULE.with(());
+    let app_window_maybe = create_app_window_checkbox(browsers, app_window_save_command);
+
     let profile_row = Flex::row()
         .with_child(profile_label)
         .with_child(selected_profile)
         .with_child(incognito_maybe)
+        .with_child(app_window_maybe)
         .padding((0.0, 10.0, 0.0, 0.0));
 
     return Container::new(
@@ -210,6 +255,77 @@ fn default_app(browsers: &Arc<Vec<UIBrowser>>) -> impl Widget<UISettings> + use<
     .expand_width();
 }
 
+// rules suggested from the accounts profiles are signed in to;
+// rebuilt when rules change, so a suggestion is hidden once a rule with the same url pattern exists
+fn suggested_rules(
+    browsers: &Arc<Vec<UIBrowser>>,
+) -> ViewSwitcher<UISettings, Arc<Vec<UISettingsRule>>> {
+    let browsers = browsers.clone();
+    return ViewSwitcher::new(
+        |data: &UISettings, _env| data.rules.clone(),
+        move |rules, _data, _env| Box::new(suggested_rules_list(&browsers, rules)),
+    );
+}
+
+fn suggested_rules_list(
+    browsers: &Arc<Vec<UIBrowser>>,
+    rules: &Arc<Vec<UISettingsRule>>,
+) -> impl Widget<UISettings> + use<> {
+    let existing_url_patterns: Vec<String> = rules
+        .iter()
+        .filter(|rule| !rule.deleted)
+        .map(|rule| rule.url_pattern.clone())
+        .collect();
+    let accounts: Vec<ProfileAccount> = browsers
+        .iter()
+        .filter(|b| b.account_email.is_some() || b.account_domain.is_some())
+        .map(|b| ProfileAccount {
+            profile_unique_id: b.unique_id.clone(),
+            email: b.account_email.clone(),
+            domain: b.account_domain.clone(),
+        })
+        .collect();
+    let suggestions = account_rules::suggest_rules(&accounts, &existing_url_patterns);
+
+    let mut col = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+    if suggestions.is_empty() {
+        return col.padding(0.0);
+    }
+
+    col = col.with_child(Label::new("Suggested rules").with_font(FONT));
+
+    for suggestion in suggestions {
+        let profile_name = find_browser(browsers, suggestion.profile_unique_id.clone())
+            .map(|b| b.get_full_name())
+            .unwrap_or("Unknown".to_string());
+        let suggestion_label =
+            Label::new(format!("{} → {}", suggestion.url_pattern, profile_name)).with_font(FONT);
+
+        let url_pattern = suggestion.url_pattern.clone();
+        let profile_unique_id = suggestion.profile_unique_id.clone();
+        let accept_button = Button::from_label(Label::new("Add").with_font(FONT)).on_click(
+            move |_ctx, data: &mut UISettings, _env| {
+                // AddRuleController of the new rule saves it
+                let opener = Some(UIProfileAndIncognito {
+                    profile: profile_unique_id.clone(),
+                    incognito: false,
+                    app_window: false,
+                });
+                data.add_rule(url_pattern.as_str(), opener);
+            },
+        );
+
+        let suggestion_row = Flex::row()
+            .with_child(accept_button)
+            .with_spacer(5.0)
+            .with_child(suggestion_label)
+            .padding((0.0, 5.0, 0.0, 0.0));
+        col = col.with_child(suggestion_row);
+    }
+
+    return col.padding((10.0, 0.0, 20.0, 10.0));
+}
+
 // handles scrolling and saving when Add Rule is pressed
 struct AddRuleController;
 
@@ -279,10 +395,19 @@ fn create_rule(browsers: &Arc<Vec<UIBrowser>>) -> impl Widget<UISettingsRule> +
         create_incognito_checkbox(browsers, save_incognito_command).lens(UISettingsRule::opener),
     );
 
+    let save_app_window_command = SAVE_RULES.with(());
+    let app_window_maybe = EnvScope::new(
+        |env, rule: &UISettingsRule| {
+            env.set(RULE_INDEX_KEY.clone(), rule.index as u64);
+        },
+        create_app_window_checkbox(browsers, save_app_window_command).lens(UISettingsRule::opener),
+    );
+
     let profile_row = Flex::row()
         .with_child(profile_label)
         .with_child(selected_profile)
         .with_child(incognito_maybe)
+        .with_child(app_window_maybe)
         .padding((0.0, 10.0, 0.0, 0.0));
 
     return Either::new(|data: &UISettingsRule, _env| data.deleted, Flex::column(), {
@@ -358,6 +483,7 @@ fn item_profile(b: &UIBrowser, save_command: Command) -> MenuItem<Option<UIProfi
                     let option: Option<UIProfileAndIncognito> = Some(UIProfileAndIncognito {
                         profile: profile_id_clone.clone(),
                         incognito: false,
+                        app_window: false,
                     });
                     *opener = option;
                 }
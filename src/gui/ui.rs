@@ -13,16 +13,21 @@ use druid::{Application, Code, Modifiers, Monitor, WindowHandle};
 use tracing::{debug, info, instrument};
 use url::Url;
 
+use crate::browser_repository::AppOS;
 use crate::gui::main_window::{
-    calculate_window_position, recalculate_window_size, COPY_LINK_TO_CLIPBOARD, HIDE_ALL_PROFILES,
-    HIDE_PROFILE, MOVE_PROFILE, OPEN_LINK_IN_BROWSER, REFRESH, RESTORE_HIDDEN_PROFILE,
-    SET_BROWSERS_AS_DEFAULT_BROWSER, SET_FOCUSED_INDEX, SHOW_ABOUT_DIALOG, SHOW_SETTINGS_DIALOG,
+    calculate_window_position, recalculate_window_size, COPY_LAUNCH_COMMAND,
+    COPY_LINK_TO_CLIPBOARD, HIDE_ALL_PROFILES, HIDE_PROFILE, MOVE_PROFILE, OPEN_LINK_IN_BROWSER,
+    REFRESH, RESTORE_HIDDEN_PROFILE, SET_BROWSERS_AS_DEFAULT_BROWSER, SET_FOCUSED_INDEX,
+    SHOW_ABOUT_DIALOG, SHOW_SETTINGS_DIALOG,
 };
 use crate::gui::ui::SettingsTab::GENERAL;
 use crate::gui::{about_dialog, main_window, settings_window, ui_theme};
 use crate::url_rule::UrlGlobMatcher;
-use crate::utils::{BehavioralConfig, Config, ConfiguredTheme, ProfileAndOptions, UIConfig};
-use crate::{CommonBrowserProfile, MessageToMain};
+use crate::utils::{
+    BehavioralConfig, Config, ConfiguredTheme, MobileHostMapping, ProfileAndOptions,
+    ProfileSortMode, TemporaryProfilesConfig, UIConfig,
+};
+use crate::{CommonBrowserProfile, MessageToMain, VisibleAndHiddenProfiles};
 
 pub struct UI {
     localizations_basedir: PathBuf,
@@ -64,7 +69,10 @@ impl UI {
             default_opener: default_opener,
             rules: Arc::new(ui_settings_rules),
             visual_settings: Self::map_as_visual_settings(config.get_ui_config()),
-            behavioral_settings: Self::map_as_ui_behavioural_settings(config.get_behavior()),
+            behavioral_settings: Self::map_as_ui_behavioural_settings(
+                config.get_behavior(),
+                config.get_temporary_profiles(),
+            ),
         };
     }
     fn map_as_visual_settings(ui_config: &UIConfig) -> UIVisualSettings {
@@ -72,12 +80,20 @@ impl UI {
             show_hotkeys: ui_config.show_hotkeys,
             quit_on_lost_focus: ui_config.quit_on_lost_focus,
             theme: ui_config.theme,
+            profile_sort: ui_config.profile_sort,
         }
     }
 
-    fn map_as_ui_behavioural_settings(behavior: &BehavioralConfig) -> UIBehavioralSettings {
+    fn map_as_ui_behavioural_settings(
+        behavior: &BehavioralConfig,
+        temporary_profiles: &TemporaryProfilesConfig,
+    ) -> UIBehavioralSettings {
         UIBehavioralSettings {
             unwrap_urls: behavior.unwrap_urls,
+            canonicalize_amp_urls: behavior.canonicalize_amp_urls,
+            canonicalize_mobile_urls: behavior.canonicalize_mobile_urls,
+            mobile_hosts: Arc::new(behavior.mobile_hosts.clone()),
+            temporary_profiles_enabled: temporary_profiles.enabled,
         }
     }
 
@@ -87,6 +103,7 @@ impl UI {
         return profile_and_options.as_ref().map(|p| UIProfileAndIncognito {
             profile: p.profile.clone(),
             incognito: p.incognito,
+            app_window: p.app_window,
         });
     }
 
@@ -121,10 +138,15 @@ impl UI {
                     .has_real_profiles()
                     .then(|| p.get_profile_name().to_string()),
                 supports_incognito: p.get_browser_common().supports_incognito(),
+                supports_app_window: p.get_browser_common().supports_app_window(),
                 icon_path: p.get_browser_icon_path().to_string(),
                 profile_icon_path: p
                     .get_profile_icon_path()
                     .map_or("".to_string(), |a| a.to_string()),
+                container_color: p.get_profile_container_color().cloned(),
+                container_icon: p.get_profile_container_icon().cloned(),
+                account_email: p.get_profile_account_email().cloned(),
+                account_domain: p.get_profile_account_domain().cloned(),
                 unique_id: p.get_unique_id(),
                 unique_app_id: p.get_unique_app_id(),
                 filtered_index: i, // TODO: filter against current url
@@ -185,6 +207,7 @@ impl UI {
             selected_browser: "".to_string(),
             focused_index: None,
             incognito_mode: false,
+            app_window_mode: false,
             browsers: self.ui_browsers.clone(),
             filtered_browsers: self.filtered_browsers.clone(),
             restorable_app_profiles: self.restorable_app_profiles.clone(),
@@ -194,12 +217,18 @@ impl UI {
         };
     }
 
-    pub fn print_visible_options(&self) {
+    // with the command each option would run, e.g for checking the setup without the GUI
+    pub fn print_visible_options(&self, visible_and_hidden_profiles: &VisibleAndHiddenProfiles) {
+        let visible_browser_profiles = &visible_and_hidden_profiles.visible_browser_profiles;
         println!("BROWSERS");
         println!();
 
         for ui_browser in self.filtered_browsers.iter() {
-            println!("{}", ui_browser.get_full_name())
+            println!("{}", ui_browser.get_full_name());
+            if let Some(p) = visible_browser_profiles.get(ui_browser.browser_profile_index) {
+                let launch_plan = p.create_launch_plan(self.url.as_str(), false, false, true);
+                println!("  {}", launch_plan.to_command_line(AppOS::current()));
+            }
         }
     }
 }
@@ -210,6 +239,7 @@ pub struct UIState {
     selected_browser: String,
     focused_index: Option<usize>,
     incognito_mode: bool,
+    app_window_mode: bool,
 
     browsers: Arc<Vec<UIBrowser>>,
 
@@ -239,17 +269,25 @@ pub struct UIVisualSettings {
     pub show_hotkeys: bool,
     pub quit_on_lost_focus: bool,
     pub theme: ConfiguredTheme,
+    pub profile_sort: ProfileSortMode,
 }
 
 #[derive(Clone, Debug, Data, Lens)]
 pub struct UIBehavioralSettings {
     pub unwrap_urls: bool,
+    pub canonicalize_amp_urls: bool,
+    pub canonicalize_mobile_urls: bool,
+    // not editable in UI, but kept so saving settings doesn't lose them
+    pub mobile_hosts: Arc<Vec<MobileHostMapping>>,
+    // saved in the temporary profiles config
+    pub temporary_profiles_enabled: bool,
 }
 
 #[derive(Clone, Debug, Data, Lens)]
 pub struct UIProfileAndIncognito {
     pub profile: String,
     pub incognito: bool,
+    pub app_window: bool,
 }
 
 #[derive(Clone, PartialEq, Data, Copy)]
@@ -262,7 +300,14 @@ pub enum SettingsTab {
 impl UISettings {
     pub fn add_empty_rule(&mut self) -> &UISettingsRule {
         info!("add_empty_rule called");
+        return self.add_rule("", None);
+    }
 
+    pub fn add_rule(
+        &mut self,
+        url_pattern: &str,
+        opener: Option<UIProfileAndIncognito>,
+    ) -> &UISettingsRule {
         let next_index = self.rules.len();
 
         let rule = UISettingsRule {
@@ -270,8 +315,8 @@ impl UISettings {
             saved: false,
             deleted: false,
             source_app: "".to_string(),
-            url_pattern: "".to_string(),
-            opener: None,
+            url_pattern: url_pattern.to_string(),
+            opener: opener,
         };
 
         let rules_mut = Arc::make_mut(&mut self.rules);
@@ -355,9 +400,19 @@ pub struct UIBrowser {
     profile_name_maybe: Option<String>,
     pub(crate) supports_profiles: bool,
     pub(crate) supports_incognito: bool,
+    pub(crate) supports_app_window: bool,
 
     icon_path: String,
     profile_icon_path: String,
+
+    // Firefox container badge, e.g "blue" and "briefcase"
+    pub(crate) container_color: Option<String>,
+    pub(crate) container_icon: Option<String>,
+
+    // signed-in account, e.g "you@acme.com" and "acme.com"
+    pub(crate) account_email: Option<String>,
+    pub(crate) account_domain: Option<String>,
+
     pub unique_id: String,
     pub(crate) unique_app_id: String,
 
@@ -405,6 +460,9 @@ pub const NEW_BROWSERS_RECEIVED: Selector<Vec<UIBrowser>> =
 pub const NEW_HIDDEN_BROWSERS_RECEIVED: Selector<Vec<UIBrowser>> =
     Selector::new("browsers.new_hidden_browsers_received");
 
+pub const LAUNCH_COMMAND_RECEIVED: Selector<String> =
+    Selector::new("browsers.launch_command_received");
+
 // or save draft?
 // or save rules, but allow "invalid" rules to be saved and handle them?
 pub const SAVE_RULES: Selector<()> = Selector::new("browsers.save_rules");
@@ -572,6 +630,9 @@ impl AppDelegate<UIState> for UIDelegate {
                 Code::ShiftLeft | Code::ShiftRight => {
                     data.incognito_mode = true;
                 }
+                Code::AltLeft | Code::AltRight => {
+                    data.app_window_mode = true;
+                }
                 Code::Digit1 | Code::Numpad1 => self.open_link_in_filtered_browser(ctx, data, 0),
                 Code::Digit2 | Code::Numpad2 => self.open_link_in_filtered_browser(ctx, data, 1),
                 Code::Digit3 | Code::Numpad3 => self.open_link_in_filtered_browser(ctx, data, 2),
@@ -589,6 +650,9 @@ impl AppDelegate<UIState> for UIDelegate {
                 Code::ShiftLeft | Code::ShiftRight => {
                     data.incognito_mode = false;
                 }
+                Code::AltLeft | Code::AltRight => {
+                    data.app_window_mode = false;
+                }
                 _ => {}
             },
 
@@ -630,6 +694,9 @@ impl AppDelegate<UIState> for UIDelegate {
             let settings = &data.ui_settings.behavioral_settings;
             let behavioral_config = BehavioralConfig {
                 unwrap_urls: settings.unwrap_urls,
+                canonicalize_amp_urls: settings.canonicalize_amp_urls,
+                canonicalize_mobile_urls: settings.canonicalize_mobile_urls,
+                mobile_hosts: settings.mobile_hosts.to_vec(),
             };
 
             self.main_sender
@@ -690,12 +757,28 @@ impl AppDelegate<UIState> for UIDelegate {
         } else if cmd.is(COPY_LINK_TO_CLIPBOARD) {
             copy_to_clipboard(data.url.as_str());
             Handled::Yes
+        } else if cmd.is(COPY_LAUNCH_COMMAND) {
+            let profile_index = cmd.get_unchecked(COPY_LAUNCH_COMMAND);
+            self.main_sender
+                .send(MessageToMain::CopyLaunchCommand(
+                    *profile_index,
+                    data.incognito_mode,
+                    data.app_window_mode,
+                    data.url.to_string(),
+                ))
+                .ok();
+            Handled::Yes
+        } else if cmd.is(LAUNCH_COMMAND_RECEIVED) {
+            let launch_command = cmd.get_unchecked(LAUNCH_COMMAND_RECEIVED);
+            copy_to_clipboard(launch_command.as_str());
+            Handled::Yes
         } else if cmd.is(OPEN_LINK_IN_BROWSER) {
             let profile_index = cmd.get_unchecked(OPEN_LINK_IN_BROWSER);
             self.main_sender
                 .send(MessageToMain::OpenLink(
                     *profile_index,
                     data.incognito_mode,
+                    data.app_window_mode,
                     data.url.to_string(),
                 ))
                 .ok();
@@ -84,6 +84,9 @@ fn get_theme(ui_theme: UITheme) -> Theme {
             hotkey_border_color: Color::rgba(0.4, 0.4, 0.4, 0.9),
             hotkey_text_color: Color::rgb8(128, 128, 128),
             options_button_text_color: Color::rgb8(128, 128, 128),
+            container_toolbar_color: Color::rgb8(0xfb, 0xfb, 0xfe),
+            container_badge_text_color: Color::rgb8(0x1c, 0x1b, 0x22),
+            container_badge_border_color: Color::rgba(0.15, 0.15, 0.15, 1.0),
         },
         settings: SettingsWindowTheme {
             active_tab_background_color: Color::rgb8(25, 90, 194),
@@ -139,6 +142,9 @@ fn get_theme(ui_theme: UITheme) -> Theme {
             hotkey_border_color: Color::rgba(0.4, 0.4, 0.4, 0.9),
             hotkey_text_color: Color::rgb8(128, 128, 128),
             options_button_text_color: Color::rgb8(128, 128, 128),
+            container_toolbar_color: Color::rgb8(0x15, 0x14, 0x1a),
+            container_badge_text_color: Color::rgb8(0xff, 0xff, 0xff),
+            container_badge_border_color: Color::rgb8(215, 215, 215),
         },
         settings: SettingsWindowTheme {
             active_tab_background_color: Color::rgb8(25, 90, 194),
@@ -206,6 +212,11 @@ pub(crate) struct MainWindowTheme {
     hotkey_border_color: Color,
     hotkey_text_color: Color,
     options_button_text_color: Color,
+    // Firefox container color "toolbar" follows the theme text color
+    container_toolbar_color: Color,
+    container_badge_text_color: Color,
+    // separates the badge from the app icon below it
+    container_badge_border_color: Color,
 }
 
 impl MainWindowTheme {
@@ -239,6 +250,30 @@ impl MainWindowTheme {
     pub const ENV_OPTIONS_BUTTON_TEXT_COLOR: Key<Color> =
         Key::new("software.browsers.theme.main.options_button_text_color");
 
+    pub const ENV_CONTAINER_TOOLBAR_COLOR: Key<Color> =
+        Key::new("software.browsers.theme.main.container_toolbar_color");
+
+    pub const ENV_CONTAINER_BADGE_TEXT_COLOR: Key<Color> =
+        Key::new("software.browsers.theme.main.container_badge_text_color");
+
+    pub const ENV_CONTAINER_BADGE_BORDER_COLOR: Key<Color> =
+        Key::new("software.browsers.theme.main.container_badge_border_color");
+
+    // Firefox container colors, see browser/components/contextualidentity in gecko
+    pub fn container_color(color_name: &str, env: &Env) -> Color {
+        return match color_name {
+            "blue" => Color::rgb8(0x37, 0xad, 0xff),
+            "turquoise" => Color::rgb8(0x00, 0xc7, 0x9a),
+            "green" => Color::rgb8(0x51, 0xcd, 0x00),
+            "yellow" => Color::rgb8(0xff, 0xcb, 0x00),
+            "orange" => Color::rgb8(0xff, 0x9f, 0x00),
+            "red" => Color::rgb8(0xff, 0x61, 0x3d),
+            "pink" => Color::rgb8(0xff, 0x4b, 0xda),
+            "purple" => Color::rgb8(0xaf, 0x51, 0xf5),
+            _ => env.get(Self::ENV_CONTAINER_TOOLBAR_COLOR),
+        };
+    }
+
     fn set_env_to_theme(&self, env: &mut Env) {
         env.set(Self::ENV_WINDOW_BACKGROUND_COLOR, self.window_background_color);
         env.set(Self::ENV_WINDOW_BORDER_COLOR, self.window_border_color);
@@ -253,6 +288,15 @@ impl MainWindowTheme {
             Self::ENV_OPTIONS_BUTTON_TEXT_COLOR,
             self.options_button_text_color,
         );
+        env.set(Self::ENV_CONTAINER_TOOLBAR_COLOR, self.container_toolbar_color);
+        env.set(
+            Self::ENV_CONTAINER_BADGE_TEXT_COLOR,
+            self.container_badge_text_color,
+        );
+        env.set(
+            Self::ENV_CONTAINER_BADGE_BORDER_COLOR,
+            self.container_badge_border_color,
+        );
     }
 }
 
@@ -0,0 +1,251 @@
+use std::process::Command;
+
+use crate::browser_repository::AppOS;
+
+// How the launched app keeps running independently of this app
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DetachStrategy {
+    // macOS: `open` hands the app over to LaunchServices and exits right away
+    LaunchServices,
+    // spawned as a child process, which keeps running after this app exits
+    Spawn,
+    // like Spawn, but waited for in the background to clean up after it (e.g a temporary profile)
+    SpawnAndWait,
+}
+
+// Everything needed to launch an app, so it can be inspected (or copied) without launching it
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchPlan {
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub cwd: Option<String>,
+    pub detach: DetachStrategy,
+}
+
+impl LaunchPlan {
+    // `env NAME=value program args` (e.g in snap .desktop files) becomes
+    // `program args` with the variables in `env`
+    pub fn new(program: &str, args: Vec<String>, detach: DetachStrategy) -> Self {
+        let env_assignments_count = if program == "env" || program.ends_with("/env") {
+            args.iter()
+                .take_while(|arg| to_env_assignment(arg).is_some())
+                .count()
+        } else {
+            0
+        };
+
+        let has_program_after_env = args
+            .get(env_assignments_count)
+            .is_some_and(|arg| !arg.starts_with('-'));
+        if env_assignments_count == 0 || !has_program_after_env {
+            return LaunchPlan {
+                program: program.to_string(),
+                args: args,
+                env: vec![],
+                cwd: None,
+                detach: detach,
+            };
+        }
+
+        let env = args[..env_assignments_count]
+            .iter()
+            .filter_map(|arg| to_env_assignment(arg))
+            .collect();
+
+        return LaunchPlan {
+            program: args[env_assignments_count].to_string(),
+            args: args[env_assignments_count + 1..].to_vec(),
+            env: env,
+            cwd: None,
+            detach: detach,
+        };
+    }
+
+    pub fn to_command(&self) -> Command {
+        let mut cmd = Command::new(self.program.as_str());
+        cmd.args(&self.args);
+        cmd.envs(self.env.iter().map(|(name, value)| (name, value)));
+        if let Some(cwd) = &self.cwd {
+            cmd.current_dir(cwd);
+        }
+
+        return cmd;
+    }
+
+    // quoted for the shell of the target OS, e.g
+    //   Linux/macOS: `cd /tmp && NAME=value /usr/bin/firefox 'https://example.com/?a=b&c=d'`
+    //   Windows:     `"C:\Program Files\Mozilla Firefox\firefox.exe" "https://example.com/?a=b&c=d"`
+    pub fn to_command_line(&self, target_os: AppOS) -> String {
+        let quote: fn(&str) -> String = match target_os {
+            AppOS::WINDOWS => quote_windows_arg,
+            AppOS::LINUX | AppOS::MAC => quote_unix_arg,
+        };
+
+        let mut parts: Vec<String> = self
+            .env
+            .iter()
+            .map(|(name, value)| match target_os {
+                AppOS::WINDOWS => {
+                    format!("set {} &&", quote(format!("{}={}", name, value).as_str()))
+                }
+                AppOS::LINUX | AppOS::MAC => format!("{}={}", name, quote(value)),
+            })
+            .collect();
+        parts.push(quote(self.program.as_str()));
+        parts.extend(self.args.iter().map(|arg| quote(arg)));
+        let command_line = parts.join(" ");
+
+        let Some(cwd) = &self.cwd else {
+            return command_line;
+        };
+        return match target_os {
+            AppOS::WINDOWS => format!("cd /d {} && {}", quote(cwd), command_line),
+            AppOS::LINUX | AppOS::MAC => format!("cd {} && {}", quote(cwd), command_line),
+        };
+    }
+}
+
+fn quote_unix_arg(arg: &str) -> String {
+    return shell_words::quote(arg).to_string();
+}
+
+// rules of CommandLineToArgvW: backslashes are literal, unless they come before a quote;
+// also quoted if cmd.exe would interpret a character, e.g `&` in urls
+fn quote_windows_arg(arg: &str) -> String {
+    let needs_quotes =
+        arg.is_empty() || arg.contains([' ', '\t', '\n', '"', '&', '|', '<', '>', '^', '(', ')']);
+    if !needs_quotes {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    let mut backslashes_count = 0;
+    for c in arg.chars() {
+        if c == '\\' {
+            backslashes_count += 1;
+            continue;
+        }
+        if c == '"' {
+            // backslashes before a quote are escaped, and so is the quote itself
+            quoted.push_str("\\".repeat(backslashes_count * 2 + 1).as_str());
+        } else {
+            quoted.push_str("\\".repeat(backslashes_count).as_str());
+        }
+        backslashes_count = 0;
+        quoted.push(c);
+    }
+    // backslashes before the closing quote are escaped too
+    quoted.push_str("\\".repeat(backslashes_count * 2).as_str());
+    quoted.push('"');
+
+    return quoted;
+}
+
+// "NAME=value" -> ("NAME", "value")
+fn to_env_assignment(arg: &str) -> Option<(String, String)> {
+    let (name, value) = arg.split_once('=')?;
+    let is_valid_name = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_valid_name {
+        return None;
+    }
+
+    return Some((name.to_string(), value.to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_strings(args: &[&str]) -> Vec<String> {
+        return args.iter().map(|arg| arg.to_string()).collect();
+    }
+
+    #[test]
+    fn test_env_prefix() {
+        let plan = LaunchPlan::new(
+            "env",
+            to_strings(&[
+                "BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/firefox_firefox.desktop",
+                "/snap/bin/firefox",
+                "https://example.com/",
+            ]),
+            DetachStrategy::Spawn,
+        );
+        assert_eq!(plan.program, "/snap/bin/firefox");
+        assert_eq!(plan.args, to_strings(&["https://example.com/"]));
+        assert_eq!(
+            plan.env,
+            vec![(
+                "BAMF_DESKTOP_FILE_HINT".to_string(),
+                "/var/lib/snapd/desktop/applications/firefox_firefox.desktop".to_string()
+            )]
+        );
+
+        // env options are kept as they are
+        let plan = LaunchPlan::new(
+            "/usr/bin/env",
+            to_strings(&["-u", "WAYLAND_DISPLAY", "chromium"]),
+            DetachStrategy::Spawn,
+        );
+        assert_eq!(plan.program, "/usr/bin/env");
+        assert!(plan.env.is_empty());
+    }
+
+    #[test]
+    fn test_to_command_line() {
+        let mut plan = LaunchPlan::new(
+            "env",
+            to_strings(&[
+                "MOZ_ENABLE_WAYLAND=1",
+                "/usr/bin/firefox",
+                "-P",
+                "Work profile",
+                "https://example.com/?a=b&c=d",
+            ]),
+            DetachStrategy::Spawn,
+        );
+        assert_eq!(
+            plan.to_command_line(AppOS::LINUX),
+            "MOZ_ENABLE_WAYLAND=1 /usr/bin/firefox -P 'Work profile' 'https://example.com/?a=b&c=d'"
+        );
+
+        plan.cwd = Some("/home/user/My Projects".to_string());
+        assert!(plan
+            .to_command_line(AppOS::LINUX)
+            .starts_with("cd '/home/user/My Projects' && MOZ_ENABLE_WAYLAND=1 "));
+    }
+
+    #[test]
+    fn test_to_command_line_windows() {
+        let mut plan = LaunchPlan::new(
+            r"C:\Program Files\Mozilla Firefox\firefox.exe",
+            to_strings(&["-P", "Work profile", "https://example.com/?a=b&c=d"]),
+            DetachStrategy::Spawn,
+        );
+        assert_eq!(
+            plan.to_command_line(AppOS::WINDOWS),
+            r#""C:\Program Files\Mozilla Firefox\firefox.exe" -P "Work profile" "https://example.com/?a=b&c=d""#
+        );
+
+        plan.cwd = Some(r"C:\Users\me\My Projects".to_string());
+        assert!(plan
+            .to_command_line(AppOS::WINDOWS)
+            .starts_with(r#"cd /d "C:\Users\me\My Projects" && "C:\Program Files"#));
+    }
+
+    #[test]
+    fn test_quote_windows_arg() {
+        assert_eq!(quote_windows_arg("-P"), "-P");
+        assert_eq!(quote_windows_arg(r"C:\dir\file"), r"C:\dir\file");
+        assert_eq!(quote_windows_arg(""), r#""""#);
+        assert_eq!(quote_windows_arg(r#"say "hi""#), r#""say \"hi\"""#);
+        // backslashes are escaped only before a quote
+        assert_eq!(quote_windows_arg(r#"a\"b"#), r#""a\\\"b""#);
+        assert_eq!(quote_windows_arg(r"C:\My Dir\"), r#""C:\My Dir\\""#);
+    }
+}
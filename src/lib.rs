@@ -1,23 +1,29 @@
 use druid::{ExtEventSink, Target, UrlOpenInfo};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
+use std::cmp::Reverse;
 use std::fmt::Debug;
-use std::process::{exit, Command};
+use std::path::Path;
+use std::process::exit;
 use std::str::FromStr;
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Arc;
+use std::thread;
 use tracing::{debug, info, instrument, warn};
 use url::form_urlencoded::Parse;
 use url::Url;
 
 use gui::ui;
 
-use crate::browser_repository::{SupportedApp, SupportedAppRepository};
+use crate::browser_repository::{AppOS, SupportedApp, SupportedAppRepository};
+use crate::desktop_entry_exec::ExecFieldValues;
 use crate::gui::ui::{UIBehavioralSettings, UIProfileAndIncognito, UISettingsRule};
 use crate::gui::ui::{UIVisualSettings, UI};
+use crate::launch_plan::{DetachStrategy, LaunchPlan};
 use crate::url_rule::UrlGlobMatcher;
 use crate::utils::{
-    BehavioralConfig, Config, ConfigRule, OSAppFinder, ProfileAndOptions, UIConfig,
+    BehavioralConfig, Config, ConfigRule, CustomLauncherConfig, OSAppFinder, ProfileAndOptions,
+    ProfileSortMode, TemporaryProfilesConfig, UIConfig, WebmailTargetConfig,
 };
 
 mod gui;
@@ -36,11 +42,24 @@ mod linux;
 #[cfg(target_os = "windows")]
 mod windows;
 
+mod account_rules;
+mod basedir_profiles_parser;
 mod chromium_profiles_parser;
+mod custom_launcher;
+mod desktop_entry_exec;
 mod firefox_profiles_parser;
+mod launch_plan;
+mod mailto_url_parser;
+mod profile_lock;
 mod slack_profiles_parser;
 mod slack_url_parser;
+mod temporary_profiles;
+#[cfg(test)]
+mod test_utils;
+mod url_canonicalizer;
 mod url_rule;
+mod url_transform;
+mod webmail_target;
 
 // a browser (with profiles), or Spotify, Zoom, etc
 pub struct GenericApp {
@@ -49,7 +68,12 @@ pub struct GenericApp {
 }
 
 impl GenericApp {
-    fn new(installed_browser: &InstalledBrowser, app_repository: &SupportedAppRepository) -> Self {
+    fn new(
+        installed_browser: &InstalledBrowser,
+        app_repository: &SupportedAppRepository,
+        show_profiles_of_other_installs: bool,
+        temporary_profiles: &TemporaryProfilesConfig,
+    ) -> Self {
         let supported_app = app_repository.get_or_generate(
             installed_browser.bundle.as_str(),
             &installed_browser.restricted_domains,
@@ -58,17 +82,86 @@ impl GenericApp {
             supported_app: supported_app,
             command: installed_browser.command.clone(),
             executable_path: installed_browser.executable_path.to_string(),
+            bundle: installed_browser.bundle.to_string(),
             display_name: installed_browser.display_name.to_string(),
             icon_path: installed_browser.icon_path.to_string(),
             profiles_type: installed_browser.profiles.profiles_type.clone(),
+            custom_launcher: None,
+            flatpak_app_id: installed_browser.flatpak_app_id.clone(),
+            snap_name: installed_browser.snap_name.clone(),
+            user_data_dir: installed_browser.user_data_dir.clone(),
+            private_window_command: installed_browser.private_window_command.clone(),
+            new_window_command: installed_browser.new_window_command.clone(),
+            desktop_entry_icon: installed_browser.desktop_entry_icon.clone(),
+            desktop_file_path: installed_browser.desktop_file_path.clone(),
+            temporary_profiles: temporary_profiles.clone(),
         };
 
         let arc = Arc::new(app.clone());
         let mut profiles: Vec<CommonBrowserProfile> = Vec::new();
         for installed_profile in &installed_browser.profiles.profiles {
+            if installed_profile.locked_to_other_install && !show_profiles_of_other_installs {
+                debug!(
+                    "Skipping Profile: {:?} because it belongs to other install",
+                    installed_profile.profile_name
+                );
+                continue;
+            }
             profiles.push(CommonBrowserProfile::new(&installed_profile, arc.clone()));
         }
 
+        // extra user-data-dir instances would only duplicate the app's own temporary profile
+        if temporary_profiles.enabled
+            && app.supports_temporary_profile()
+            && installed_browser.user_data_dir.is_none()
+        {
+            let temporary_profile = temporary_profiles::temporary_profile();
+            profiles.push(CommonBrowserProfile::new(&temporary_profile, arc.clone()));
+        }
+
+        return Self {
+            app: app,
+            profiles: profiles,
+        };
+    }
+
+    fn new_custom_launcher(
+        launcher: &CustomLauncherConfig,
+        app_repository: &SupportedAppRepository,
+    ) -> Self {
+        let unique_app_id = custom_launcher::get_custom_launcher_unique_app_id(launcher);
+        let supported_app = app_repository.get_or_generate(
+            unique_app_id.as_str(),
+            &launcher.restricted_url_patterns,
+        );
+        let placeholder_profiles = InstalledAppProfiles::new_placeholder();
+
+        let app = BrowserCommon {
+            supported_app: supported_app,
+            command: launcher.command.clone(),
+            executable_path: unique_app_id,
+            bundle: "".to_string(),
+            display_name: launcher.name.to_string(),
+            icon_path: launcher.icon.clone().unwrap_or_default(),
+            profiles_type: placeholder_profiles.profiles_type.clone(),
+            custom_launcher: Some(launcher.clone()),
+            flatpak_app_id: None,
+            snap_name: None,
+            user_data_dir: None,
+            private_window_command: None,
+            new_window_command: None,
+            desktop_entry_icon: None,
+            desktop_file_path: None,
+            temporary_profiles: TemporaryProfilesConfig::default(),
+        };
+
+        let arc = Arc::new(app.clone());
+        let profiles: Vec<CommonBrowserProfile> = placeholder_profiles
+            .profiles
+            .iter()
+            .map(|installed_profile| CommonBrowserProfile::new(installed_profile, arc.clone()))
+            .collect();
+
         return Self {
             app: app,
             profiles: profiles,
@@ -84,15 +177,34 @@ impl GenericApp {
 pub struct BrowserCommon {
     command: Vec<String>,
     executable_path: String,
+    // macOS: bundle id which `open -b` launches
+    bundle: String,
     display_name: String,
     icon_path: String,
     supported_app: SupportedApp,
     profiles_type: InstalledAppProfilesType,
+    // set if this is a user-defined launcher instead of an installed app
+    custom_launcher: Option<CustomLauncherConfig>,
+    flatpak_app_id: Option<String>,
+    snap_name: Option<String>,
+    // Chromium-based: extra `--user-data-dir` instance of the app
+    user_data_dir: Option<String>,
+    // Linux only; commands of the private window and new window desktop actions
+    private_window_command: Option<Vec<String>>,
+    new_window_command: Option<Vec<String>>,
+    // Linux only; for the `%i` and `%k` field codes in the command
+    desktop_entry_icon: Option<String>,
+    desktop_file_path: Option<String>,
+    temporary_profiles: TemporaryProfilesConfig,
 }
 
 impl BrowserCommon {
     // used in configuration file to uniquely identify this app
     fn get_unique_app_id(&self) -> String {
+        // extra user-data-dir instances share the executable with the main instance
+        if let Some(ref user_data_dir) = self.user_data_dir {
+            return format!("{} --user-data-dir={}", self.executable_path, user_data_dir);
+        }
         return self.executable_path.to_string();
     }
 
@@ -100,8 +212,24 @@ impl BrowserCommon {
         self.profiles_type == InstalledAppProfilesType::RealProfiles
     }
 
+    // unknown browsers get private mode from their .desktop file
     fn supports_incognito(&self) -> bool {
-        return self.supported_app.supports_incognito();
+        return self.supported_app.supports_incognito() || self.private_window_command.is_some();
+    }
+
+    fn supports_app_window(&self) -> bool {
+        return self.custom_launcher.is_none() && self.supported_app.supports_app_window();
+    }
+
+    // sandboxed flatpak and snap apps can't access the runtime dir of this app;
+    // without real profiles the picker wouldn't show the profile name
+    fn supports_temporary_profile(&self) -> bool {
+        return self.has_real_profiles()
+            && self.flatpak_app_id.is_none()
+            && self.snap_name.is_none()
+            && !self.supported_app.is_mail_client()
+            && (self.supported_app.supports_user_data_dir()
+                || self.supported_app.supports_profile_dir());
     }
 
     fn get_browser_icon_path(&self) -> &str {
@@ -112,110 +240,272 @@ impl BrowserCommon {
         return self.display_name.as_str();
     }
 
-    fn create_command(
+    // fresh profile directory for every launch; not created on dry run
+    fn get_temporary_profile_args(&self, dry_run: bool) -> Vec<String> {
+        if self.supported_app.supports_user_data_dir() {
+            let user_data_dir = temporary_profiles::create_chromium_temporary_profile(
+                &self.temporary_profiles.chromium_prefs,
+                dry_run,
+            );
+            return self
+                .supported_app
+                .get_user_data_dir_args(user_data_dir.to_string_lossy().as_ref());
+        }
+
+        let profile_dir = temporary_profiles::create_firefox_temporary_profile(
+            &self.temporary_profiles.firefox_prefs,
+            dry_run,
+        );
+        let mut profile_args = self
+            .supported_app
+            .get_profile_dir_args(profile_dir.to_string_lossy().as_ref());
+        profile_args.extend_from_slice(self.supported_app.get_new_instance_args());
+        return profile_args;
+    }
+
+    fn get_profile_args(
         &self,
         common_browser_profile: &CommonBrowserProfile,
-        url: &str,
-        incognito_mode: bool,
-    ) -> Command {
-        let profile_cli_arg_value: &str = &common_browser_profile.profile_cli_arg_value;
-        let profile_args = self.supported_app.get_profile_args(profile_cli_arg_value);
-        let app_url = self
-            .supported_app
-            .get_transformed_url(common_browser_profile, url);
+        dry_run: bool,
+    ) -> Vec<String> {
+        if common_browser_profile.profile_is_temporary {
+            return self.get_temporary_profile_args(dry_run);
+        }
 
-        let (main_command, command_arguments) = self.command.split_at(1);
-        let main_command = main_command.first().unwrap(); // guaranteed to not be empty
+        let profile_dir_maybe = common_browser_profile.profile_dir.as_ref();
 
-        // TODO: support BSD - https://doc.rust-lang.org/reference/conditional-compilation.html
-        if cfg!(target_os = "macos") {
-            let mut cmd = Command::new("open");
+        let mut profile_args = match &self.user_data_dir {
+            Some(user_data_dir) => self
+                .supported_app
+                .get_user_data_dir_args(user_data_dir.as_str()),
+            None => vec![],
+        };
 
-            let arguments = cmd.arg("-b").arg(&self.supported_app.get_app_id());
+        profile_args.extend(match profile_dir_maybe {
+            Some(profile_dir) if common_browser_profile.profile_launch_by_dir => self
+                .supported_app
+                .get_profile_dir_args(profile_dir.as_str()),
+            _ => self
+                .supported_app
+                .get_profile_args(&common_browser_profile.profile_cli_arg_value),
+        });
 
-            if !self.supported_app.is_url_as_first_arg() {
-                // e.g Safari requires url to be as the apple event
-                arguments.arg(app_url.clone());
-            } else {
-                // no direct link between !is_url_as_first_arg,
-                // but mostly for Safari so it wont open new window
-                // and all other not special apps
-                arguments.arg("-n");
+        // Firefox sends the url to an already running instance, even if it runs another profile,
+        // so a profile which is not running yet is started as a separate instance;
+        // the lock is checked only on a real launch, the shown command doesn't depend on it
+        let checks_profile_lock = !dry_run && self.supported_app.supports_new_instance();
+        if let Some(profile_dir) = profile_dir_maybe.filter(|_| checks_profile_lock) {
+            let is_running = profile_lock::is_firefox_profile_locked(Path::new(profile_dir));
+            if !is_running {
+                profile_args.extend_from_slice(self.supported_app.get_new_instance_args());
             }
+        }
 
-            arguments.arg("--args");
-            arguments.args(profile_args);
+        return profile_args;
+    }
 
-            if incognito_mode && self.supported_app.supports_incognito() {
-                let incognito_args = self.supported_app.get_incognito_args();
-                arguments.args(incognito_args);
-            }
+    // profile args are resolved here, because they can create a temporary profile
+    // or depend on whether the profile is running;
+    // dry run is for showing the command, so it doesn't create the temporary profile
+    fn create_launch_plan(
+        &self,
+        common_browser_profile: &CommonBrowserProfile,
+        url: &str,
+        incognito_mode: bool,
+        app_window_mode: bool,
+        dry_run: bool,
+    ) -> LaunchPlan {
+        let profile_args = match self.custom_launcher {
+            Some(_) => vec![],
+            None => self.get_profile_args(common_browser_profile, dry_run),
+        };
 
-            if self.supported_app.is_url_as_first_arg() {
-                arguments.arg(app_url.clone());
-            }
+        return self.to_launch_plan(
+            common_browser_profile,
+            url,
+            profile_args,
+            incognito_mode,
+            app_window_mode,
+            AppOS::current(),
+        );
+    }
 
-            debug!("Launching: {:?}", cmd);
-            return cmd;
-        } else if cfg!(target_os = "linux") {
-            let has_url_placeholder = command_arguments
-                .iter()
-                .any(|arg| arg.eq_ignore_ascii_case("%u"));
+    // doesn't touch the system, so plans for every OS can be built (and tested) on any OS
+    fn to_launch_plan(
+        &self,
+        common_browser_profile: &CommonBrowserProfile,
+        url: &str,
+        mut profile_args: Vec<String>,
+        incognito_mode: bool,
+        app_window_mode: bool,
+        target_os: AppOS,
+    ) -> LaunchPlan {
+        if let Some(ref launcher) = self.custom_launcher {
+            return custom_launcher::create_custom_launcher_plan(launcher, url);
+        }
 
-            let arguments = if has_url_placeholder {
-                replace_url_placeholder(command_arguments, app_url.as_str())
+        let app_url = self
+            .supported_app
+            .get_transformed_url(common_browser_profile, url);
+        // mail client gets the mailto url right after an argument, e.g `-compose <mailto url>`
+        let url_prefix_args: &[String] =
+            if url.starts_with("mailto:") && self.supported_app.is_mail_client() {
+                self.supported_app.get_mailto_args()
             } else {
-                command_arguments.to_vec()
+                &[]
             };
+        let app_url = if app_window_mode && self.supported_app.supports_app_window() {
+            // app window mode passes the url inside an argument instead, e.g `--app=<url>`
+            self.supported_app.get_app_window_arg(app_url.as_str())
+        } else {
+            app_url
+        };
 
-            let mut cmd = Command::new(main_command.to_string());
+        let is_private_window = incognito_mode && self.supports_incognito();
 
-            // this might mess up the command,
-            // if `main_command` is not yet the actual program that takes the incognito argument;
-            // that's because the actual program might be in `arguments` (depends what's in the .desktop file)
-            if incognito_mode && self.supported_app.supports_incognito() {
-                let incognito_args = self.supported_app.get_incognito_args();
-                cmd.args(incognito_args);
-            }
+        // desktop actions already contain the private window or new window arguments,
+        // so those don't need to be injected into the command;
+        // new window action is for apps which the repository has no arguments for
+        let desktop_action_command_maybe = if is_private_window {
+            self.private_window_command.as_ref()
+        } else if !self.supported_app.has_launch_args() {
+            self.new_window_command.as_ref()
+        } else {
+            None
+        };
+        let command = desktop_action_command_maybe.unwrap_or(&self.command);
+        let incognito_args: &[String] =
+            if is_private_window && desktop_action_command_maybe.is_none() {
+                self.supported_app.get_incognito_args()
+            } else {
+                &[]
+            };
 
-            cmd.args(arguments);
-            cmd.args(profile_args);
+        // private windows are opened as new windows anyway
+        if !is_private_window && desktop_action_command_maybe.is_none() {
+            profile_args.extend_from_slice(self.supported_app.get_new_window_args());
+        }
 
-            // Non-browser apps don't have the placeholder
-            if !has_url_placeholder {
-                cmd.arg(app_url);
-            }
+        // temporary profile is removed once the browser exits
+        let detach = if common_browser_profile.profile_is_temporary {
+            DetachStrategy::SpawnAndWait
+        } else {
+            DetachStrategy::Spawn
+        };
+
+        let (main_command, command_arguments) = command.split_at(1);
+        let main_command = main_command.first().unwrap(); // guaranteed to not be empty
+
+        // TODO: support BSD - https://doc.rust-lang.org/reference/conditional-compilation.html
+        return match target_os {
+            AppOS::MAC => {
+                let mut arguments: Vec<String> = vec!["-b".to_string(), self.bundle.to_string()];
+
+                if !self.supported_app.is_url_as_first_arg() {
+                    // e.g Safari requires url to be as the apple event
+                    arguments.push(app_url.clone());
+                } else {
+                    // no direct link between !is_url_as_first_arg,
+                    // but mostly for Safari so it wont open new window
+                    // and all other not special apps
+                    arguments.push("-n".to_string());
+                }
 
-            return cmd;
-        } else if cfg!(target_os = "windows") {
-            let mut cmd = Command::new(main_command.to_string());
-            cmd.args(profile_args);
+                arguments.push("--args".to_string());
+                arguments.extend(profile_args);
+                arguments.extend_from_slice(incognito_args);
 
-            if incognito_mode && self.supported_app.supports_incognito() {
-                let incognito_args = self.supported_app.get_incognito_args();
-                cmd.args(incognito_args);
+                if self.supported_app.is_url_as_first_arg() {
+                    arguments.extend_from_slice(url_prefix_args);
+                    arguments.push(app_url);
+                }
+
+                // `open` exits right away, so a temporary profile is removed on next start instead
+                LaunchPlan::new("open", arguments, DetachStrategy::LaunchServices)
             }
+            AppOS::LINUX => {
+                let has_url_placeholder = desktop_entry_exec::has_url_field_code(command_arguments);
+                let exec_field_values = ExecFieldValues {
+                    url: app_url.as_str(),
+                    icon: self.desktop_entry_icon.as_deref(),
+                    name: self.display_name.as_str(),
+                    desktop_file_path: self.desktop_file_path.as_deref(),
+                };
 
-            cmd.arg(app_url);
+                // expanded separately, so the index stays valid when field codes expand to
+                // more or less arguments (e.g `%i` -> `--icon firefox`)
+                let profile_args_index = find_profile_args_index(command_arguments);
+                let (arguments_before_profile, arguments_after_profile) =
+                    command_arguments.split_at(profile_args_index);
+                let mut arguments = desktop_entry_exec::expand_field_codes(
+                    arguments_before_profile,
+                    &exec_field_values,
+                );
+                let profile_args_index = arguments.len();
+                arguments.extend(desktop_entry_exec::expand_field_codes(
+                    arguments_after_profile,
+                    &exec_field_values,
+                ));
+
+                // flatpak run [flatpak options] <app-id> [app arguments]
+                // app arguments must come after the app id, otherwise flatpak tries to interpret them
+                let flatpak_app_id_index_maybe = self
+                    .flatpak_app_id
+                    .as_ref()
+                    .and_then(|app_id| arguments.iter().position(|arg| arg == app_id));
+                let mut launch_arguments: Vec<String> = Vec::new();
+                if let Some(flatpak_app_id_index) = flatpak_app_id_index_maybe {
+                    let (flatpak_arguments, app_arguments) =
+                        arguments.split_at(flatpak_app_id_index + 1);
+                    launch_arguments.extend_from_slice(flatpak_arguments);
+                    launch_arguments.extend_from_slice(incognito_args);
+                    launch_arguments.extend(profile_args);
+                    launch_arguments.extend_from_slice(app_arguments);
+                } else {
+                    // this might mess up the command,
+                    // if `main_command` is not yet the actual program that takes the incognito argument;
+                    // that's because the actual program might be in `arguments` (depends what's in the .desktop file);
+                    // not needed if the .desktop file has a private window action
+                    launch_arguments.extend_from_slice(incognito_args);
+
+                    // profile args go after the .desktop file arguments,
+                    // except before `--untrusted-args`, which must be the last option
+                    arguments.splice(profile_args_index..profile_args_index, profile_args);
+                    launch_arguments.extend(arguments);
+                }
 
-            return cmd;
-        }
+                // Non-browser apps don't have the placeholder
+                if !has_url_placeholder {
+                    launch_arguments.extend_from_slice(url_prefix_args);
+                    launch_arguments.push(app_url);
+                } else if let Some(url_index) =
+                    launch_arguments.iter().position(|arg| arg == &app_url)
+                {
+                    launch_arguments.splice(url_index..url_index, url_prefix_args.to_vec());
+                }
+
+                LaunchPlan::new(main_command.as_str(), launch_arguments, detach)
+            }
+            AppOS::WINDOWS => {
+                let mut arguments: Vec<String> = profile_args;
+                arguments.extend_from_slice(incognito_args);
+                arguments.extend_from_slice(url_prefix_args);
+                arguments.push(app_url);
 
-        unimplemented!("platform is not supported yet");
+                LaunchPlan::new(main_command.as_str(), arguments, detach)
+            }
+        };
     }
 }
 
-fn replace_url_placeholder(command_arguments: &[String], app_url: &str) -> Vec<String> {
-    return command_arguments
+// where profile args are inserted into the .desktop file arguments: at the end,
+// or right before `--untrusted-args` (qutebrowser takes every argument after it as a url)
+fn find_profile_args_index(command_arguments: &[String]) -> usize {
+    let untrusted_args_index_maybe = command_arguments
         .iter()
-        .map(|arg| {
-            if arg.eq_ignore_ascii_case("%u") {
-                app_url.to_string()
-            } else {
-                arg.to_string()
-            }
-        })
-        .collect();
+        .position(|arg| arg == "--untrusted-args");
+
+    return untrusted_args_index_maybe.unwrap_or(command_arguments.len());
 }
 
 #[derive(Clone)]
@@ -224,7 +514,18 @@ pub struct CommonBrowserProfile {
     profile_cli_container_name: Option<String>,
     profile_name: String,
     profile_icon: Option<String>,
+    profile_container_color: Option<String>,
+    profile_container_icon: Option<String>,
+    profile_account_email: Option<String>,
+    profile_account_domain: Option<String>,
+    profile_last_used: Option<u64>,
+    profile_is_browser_default: bool,
+    profile_dir: Option<String>,
+    profile_launch_by_dir: bool,
+    profile_is_temporary: bool,
     profile_restricted_url_matchers: Vec<UrlGlobMatcher>,
+    // set if this is a webmail which mailto urls are composed in
+    webmail_target: Option<WebmailTargetConfig>,
     app: Arc<BrowserCommon>,
 }
 
@@ -244,11 +545,36 @@ impl CommonBrowserProfile {
                 .profile_icon
                 .as_ref()
                 .map(|path| path.clone()),
+            profile_container_color: installed_browser_profile.profile_container_color.clone(),
+            profile_container_icon: installed_browser_profile.profile_container_icon.clone(),
+            profile_account_email: installed_browser_profile.profile_account_email.clone(),
+            profile_account_domain: installed_browser_profile.profile_account_domain.clone(),
+            profile_last_used: installed_browser_profile.profile_last_used,
+            profile_is_browser_default: installed_browser_profile.profile_is_browser_default,
+            profile_dir: installed_browser_profile.profile_dir.clone(),
+            profile_launch_by_dir: installed_browser_profile.profile_launch_by_dir,
+            profile_is_temporary: installed_browser_profile.profile_is_temporary,
             profile_restricted_url_matchers: profile_restricted_url_matchers,
+            webmail_target: None,
             app: app,
         }
     }
 
+    // same browser profile, but only offered for mailto urls, which it opens in the webmail
+    fn new_webmail_target(
+        browser_profile: &CommonBrowserProfile,
+        webmail_target: &WebmailTargetConfig,
+    ) -> Self {
+        let mut webmail_profile = browser_profile.clone();
+        if !webmail_target.name.is_empty() {
+            webmail_profile.profile_name = webmail_target.name.clone();
+        }
+        webmail_profile.profile_restricted_url_matchers =
+            Self::generate_restricted_hostname_matchers(&vec!["mailto://**".to_string()]);
+        webmail_profile.webmail_target = Some(webmail_target.clone());
+        return webmail_profile;
+    }
+
     fn generate_restricted_hostname_matchers(
         restricted_url_patterns: &Vec<String>,
     ) -> Vec<UrlGlobMatcher> {
@@ -266,6 +592,10 @@ impl CommonBrowserProfile {
 
     // used in configuration file to uniquely identify this app+profile+container
     fn get_unique_id(&self) -> String {
+        if let Some(ref webmail_target) = self.webmail_target {
+            return webmail_target::get_webmail_target_unique_id(webmail_target);
+        }
+
         let app_id = self.get_unique_app_id();
         let app_and_profile = app_id + "#" + self.profile_cli_arg_value.as_str();
 
@@ -312,16 +642,70 @@ impl CommonBrowserProfile {
         return self.profile_icon.as_ref();
     }
 
+    fn get_profile_container_color(&self) -> Option<&String> {
+        return self.profile_container_color.as_ref();
+    }
+
+    fn get_profile_container_icon(&self) -> Option<&String> {
+        return self.profile_container_icon.as_ref();
+    }
+
+    fn get_profile_account_email(&self) -> Option<&String> {
+        return self.profile_account_email.as_ref();
+    }
+
+    fn get_profile_account_domain(&self) -> Option<&String> {
+        return self.profile_account_domain.as_ref();
+    }
+
     fn get_profile_name(&self) -> &str {
         return self.profile_name.as_str();
     }
 
-    fn open_link(&self, url: &str, incognito_mode: bool) {
-        let _ = &self.create_command(url, incognito_mode).spawn();
+    fn get_profile_last_used(&self) -> Option<u64> {
+        return self.profile_last_used;
+    }
+
+    fn is_browser_default_profile(&self) -> bool {
+        return self.profile_is_browser_default;
     }
 
-    fn create_command(&self, url: &str, incognito_mode: bool) -> Command {
-        return self.app.create_command(self, url, incognito_mode);
+    fn open_link(&self, url: &str, incognito_mode: bool, app_window_mode: bool) {
+        let launch_plan = self.create_launch_plan(url, incognito_mode, app_window_mode, false);
+        debug!("Launching: {:?}", launch_plan);
+        let child_result = launch_plan.to_command().spawn();
+
+        if launch_plan.detach == DetachStrategy::SpawnAndWait {
+            if let Ok(mut child) = child_result {
+                thread::spawn(move || {
+                    child.wait().ok();
+                    temporary_profiles::remove_unused_temporary_profiles();
+                });
+            }
+        }
+    }
+
+    fn create_launch_plan(
+        &self,
+        url: &str,
+        incognito_mode: bool,
+        app_window_mode: bool,
+        dry_run: bool,
+    ) -> LaunchPlan {
+        if let Some(ref webmail_target) = self.webmail_target {
+            let compose_url = webmail_target::to_compose_url(webmail_target, url);
+            return self.app.create_launch_plan(
+                self,
+                compose_url.as_str(),
+                incognito_mode,
+                app_window_mode,
+                dry_run,
+            );
+        }
+
+        return self
+            .app
+            .create_launch_plan(self, url, incognito_mode, app_window_mode, dry_run);
     }
 }
 
@@ -359,6 +743,31 @@ pub struct InstalledBrowser {
 
     #[serde(default)]
     restricted_domains: Vec<String>,
+
+    // Linux only; set if the app is run via `flatpak run <flatpak_app_id>`
+    #[serde(default)]
+    flatpak_app_id: Option<String>,
+
+    // Linux only; set if the app is a snap, e.g "firefox"
+    #[serde(default)]
+    snap_name: Option<String>,
+
+    // Chromium-based: set for the extra `--user-data-dir` instances from config
+    #[serde(default)]
+    user_data_dir: Option<String>,
+
+    // Linux only; Exec of the `new-private-window` and `new-window` actions in the .desktop file,
+    // e.g ["/usr/bin/google-chrome-stable", "--incognito"]
+    #[serde(default)]
+    private_window_command: Option<Vec<String>>,
+    #[serde(default)]
+    new_window_command: Option<Vec<String>>,
+
+    // Linux only; `Icon` and location of the .desktop file, for `%i` and `%k` in the command
+    #[serde(default)]
+    desktop_entry_icon: Option<String>,
+    #[serde(default)]
+    desktop_file_path: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -391,6 +800,16 @@ impl InstalledAppProfiles {
             profile_name: "".to_string(),
             profile_icon: None,
             profile_restricted_url_patterns: vec![],
+            locked_to_other_install: false,
+            profile_container_color: None,
+            profile_container_icon: None,
+            profile_account_email: None,
+            profile_account_domain: None,
+            profile_last_used: None,
+            profile_is_browser_default: false,
+            profile_dir: None,
+            profile_launch_by_dir: false,
+            profile_is_temporary: false,
         });
 
         return browser_profiles;
@@ -410,6 +829,42 @@ pub struct InstalledBrowserProfile {
     profile_name: String,
     profile_icon: Option<String>,
     profile_restricted_url_patterns: Vec<String>,
+
+    // Firefox-based: profile is the locked default profile of another install
+    // sharing the same profiles.ini (e.g Firefox Nightly's profile seen by Firefox)
+    #[serde(default)]
+    locked_to_other_install: bool,
+
+    // Firefox container color (e.g "blue") and icon (e.g "briefcase") from containers.json
+    #[serde(default)]
+    profile_container_color: Option<String>,
+    #[serde(default)]
+    profile_container_icon: Option<String>,
+
+    // signed-in account, e.g "you@acme.com", and its organization domain, e.g "acme.com"
+    #[serde(default)]
+    profile_account_email: Option<String>,
+    #[serde(default)]
+    profile_account_domain: Option<String>,
+
+    // when the browser last used the profile, in seconds since unix epoch
+    #[serde(default)]
+    profile_last_used: Option<u64>,
+    // profile the browser itself opens by default
+    #[serde(default)]
+    profile_is_browser_default: bool,
+
+    // Firefox-based: profile directory, to check if the profile is already running
+    #[serde(default)]
+    profile_dir: Option<String>,
+    // launch with the profile directory instead of profile_cli_arg_value,
+    // e.g when several profiles have the same name
+    #[serde(default)]
+    profile_launch_by_dir: bool,
+
+    // throwaway profile, every launch gets a fresh profile directory
+    #[serde(default)]
+    profile_is_temporary: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -557,12 +1012,31 @@ pub fn generate_all_browser_profiles(
     let mut visible_browser_profiles: Vec<CommonBrowserProfile> = Vec::new();
     let mut hidden_browser_profiles: Vec<CommonBrowserProfile> = Vec::new();
     //let support_dir = macos_get_application_support_dir();
+    let mut apps: Vec<GenericApp> = Vec::new();
     debug!("Apps");
     for installed_browser in installed_browsers {
         debug!("App: {:?}", installed_browser.bundle);
         debug!("  Path: {:?}", installed_browser.executable_path);
-        let app = GenericApp::new(&installed_browser, app_finder.get_app_repository());
+        let app = GenericApp::new(
+            &installed_browser,
+            app_finder.get_app_repository(),
+            config.get_show_profiles_of_other_installs(),
+            config.get_temporary_profiles(),
+        );
+        apps.push(app);
+    }
+
+    for launcher in config.get_custom_launchers() {
+        if launcher.command.is_empty() {
+            warn!("Skipping custom launcher {:?} without command", launcher.name);
+            continue;
+        }
+        debug!("Custom launcher: {:?}", launcher.name);
+        let app = GenericApp::new_custom_launcher(launcher, app_finder.get_app_repository());
+        apps.push(app);
+    }
 
+    for app in apps {
         for p in app.get_profiles() {
             let app_id = p.get_unique_app_id();
             if hidden_apps.contains(&app_id) {
@@ -589,8 +1063,32 @@ pub fn generate_all_browser_profiles(
         }
     }
 
+    for webmail_target in config.get_webmail_targets() {
+        let browser_profile_maybe = visible_browser_profiles
+            .iter()
+            .chain(hidden_browser_profiles.iter())
+            .find(|p| p.get_unique_id() == webmail_target.profile);
+        let Some(browser_profile) = browser_profile_maybe else {
+            warn!(
+                "Skipping webmail target {:?}, profile {:?} not found",
+                webmail_target.name, webmail_target.profile
+            );
+            continue;
+        };
+
+        let p = CommonBrowserProfile::new_webmail_target(browser_profile, webmail_target);
+        let profile_unique_id = p.get_unique_id();
+        debug!("Webmail target: {:?}", profile_unique_id.as_str());
+        if hidden_profiles.contains(&profile_unique_id) {
+            hidden_browser_profiles.push(p);
+        } else {
+            visible_browser_profiles.push(p);
+        }
+    }
+
     let profile_order = config.get_profile_order();
-    sort_browser_profiles(&mut visible_browser_profiles, profile_order);
+    let profile_sort = config.get_ui_config().profile_sort;
+    sort_browser_profiles(&mut visible_browser_profiles, profile_order, profile_sort);
 
     return VisibleAndHiddenProfiles {
         visible_browser_profiles: visible_browser_profiles,
@@ -601,9 +1099,34 @@ pub fn generate_all_browser_profiles(
 fn sort_browser_profiles(
     visible_browser_profiles: &mut Vec<CommonBrowserProfile>,
     profile_order: &Vec<String>,
+    profile_sort: ProfileSortMode,
 ) {
     let unordered_index = profile_order.len();
 
+    // natural ordering of profiles, which are not explicitly ordered
+    match profile_sort {
+        ProfileSortMode::Alphabetical => {}
+        ProfileSortMode::MostRecentlyUsed => {
+            // browsers record usage differently, so keep the browsers in place
+            // and sort only the profiles of each browser; profiles without usage info go last
+            let mut app_ids: Vec<String> = Vec::new();
+            for p in visible_browser_profiles.iter() {
+                let app_id = p.get_unique_app_id();
+                if !app_ids.contains(&app_id) {
+                    app_ids.push(app_id);
+                }
+            }
+            visible_browser_profiles.sort_by_key(|p| {
+                let app_id = p.get_unique_app_id();
+                let app_index = app_ids.iter().position(|x| x == &app_id);
+                return (app_index, Reverse(p.get_profile_last_used()));
+            });
+        }
+        ProfileSortMode::BrowserDefaultFirst => {
+            visible_browser_profiles.sort_by_key(|p| !p.is_browser_default_profile());
+        }
+    }
+
     visible_browser_profiles.sort_by_key(|p| {
         let profile_unique_id = p.get_unique_id();
         let order_maybe = profile_order.iter().position(|x| x == &profile_unique_id);
@@ -615,18 +1138,46 @@ fn sort_browser_profiles(
     visible_browser_profiles.sort_by_key(|b| !b.has_priority_ordering());
 }
 
+// temporary profiles of browsers which have exited since the last start
+pub fn remove_unused_temporary_profiles() {
+    temporary_profiles::remove_unused_temporary_profiles();
+}
+
 pub fn unwrap_url(url_str: &str, behavioral_settings: &BehavioralConfig) -> String {
-    if !behavioral_settings.unwrap_urls {
-        return url_str.to_string();
+    let mut url_string = url_str.to_string();
+
+    if behavioral_settings.unwrap_urls {
+        let unwrapped_url_maybe = Url::from_str(url_string.as_str())
+            .ok()
+            .and_then(|url| unwrap_redirect_url(&url));
+        if let Some(unwrapped_url) = unwrapped_url_maybe {
+            url_string = unwrapped_url;
+        }
     }
 
-    let url_maybe = Url::from_str(url_str).ok();
-    if url_maybe.is_none() {
-        return url_str.to_string();
+    if behavioral_settings.canonicalize_amp_urls {
+        let amp_url_maybe = Url::from_str(url_string.as_str())
+            .ok()
+            .and_then(|url| url_canonicalizer::canonicalize_amp_url(&url));
+        if let Some(publisher_url) = amp_url_maybe {
+            url_string = publisher_url;
+        }
     }
-    let url = url_maybe.unwrap();
 
-    let transformed_url = url.domain().and_then(|domain| {
+    if behavioral_settings.canonicalize_mobile_urls {
+        let desktop_url_maybe = Url::from_str(url_string.as_str()).ok().and_then(|url| {
+            url_canonicalizer::canonicalize_mobile_url(&url, &behavioral_settings.mobile_hosts)
+        });
+        if let Some(desktop_url) = desktop_url_maybe {
+            url_string = desktop_url;
+        }
+    }
+
+    return url_string;
+}
+
+fn unwrap_redirect_url(url: &Url) -> Option<String> {
+    return url.domain().and_then(|domain| {
         let domain_lowercase = domain.to_lowercase();
 
         return if domain_lowercase.ends_with("safelinks.protection.outlook.com") {
@@ -651,8 +1202,6 @@ pub fn unwrap_url(url_str: &str, behavioral_settings: &BehavioralConfig) -> Stri
             None
         };
     });
-
-    return transformed_url.unwrap_or(url_str.to_string());
 }
 
 pub fn handle_messages_to_main(
@@ -678,12 +1227,12 @@ pub fn handle_messages_to_main(
                     .submit_command(ui::NEW_BROWSERS_RECEIVED, ui_browsers, Target::Global)
                     .ok();
             }
-            MessageToMain::OpenLink(profile_index, incognito_mode, url) => {
+            MessageToMain::OpenLink(profile_index, incognito_mode, app_window_mode, url) => {
                 let option = &visible_and_hidden_profiles
                     .visible_browser_profiles
                     .get(profile_index);
                 let profile = option.unwrap();
-                profile.open_link(url.as_str(), incognito_mode);
+                profile.open_link(url.as_str(), incognito_mode, app_window_mode);
                 ui_event_sink
                     .submit_command(
                         ui::OPEN_LINK_IN_BROWSER_COMPLETED,
@@ -692,6 +1241,32 @@ pub fn handle_messages_to_main(
                     )
                     .ok();
             }
+            MessageToMain::CopyLaunchCommand(
+                profile_index,
+                incognito_mode,
+                app_window_mode,
+                url,
+            ) => {
+                let profile_maybe = visible_and_hidden_profiles
+                    .visible_browser_profiles
+                    .get(profile_index);
+                if let Some(profile) = profile_maybe {
+                    // only copied, so a temporary profile is not created
+                    let launch_plan = profile.create_launch_plan(
+                        url.as_str(),
+                        incognito_mode,
+                        app_window_mode,
+                        true,
+                    );
+                    ui_event_sink
+                        .submit_command(
+                            ui::LAUNCH_COMMAND_RECEIVED,
+                            launch_plan.to_command_line(AppOS::current()),
+                            Target::Global,
+                        )
+                        .ok();
+                }
+            }
             MessageToMain::UrlOpenRequest(from_bundle_id, url) => {
                 let url_open_info = UrlOpenInfo {
                     url: url,
@@ -742,12 +1317,13 @@ pub fn handle_messages_to_main(
                     let profile_and_options = opening_profile_id.clone();
                     let profile_id = profile_and_options.profile;
                     let incognito = profile_and_options.incognito;
+                    let app_window = profile_and_options.app_window;
 
                     let profile_maybe =
                         visible_and_hidden_profiles.get_browser_profile_by_id(profile_id.as_str());
 
                     if let Some(profile) = profile_maybe {
-                        profile.open_link(new_modified_url.as_str(), incognito);
+                        profile.open_link(new_modified_url.as_str(), incognito, app_window);
                         ui_event_sink
                             .submit_command(
                                 ui::OPEN_LINK_IN_BROWSER_COMPLETED,
@@ -866,6 +1442,7 @@ pub fn handle_messages_to_main(
                     sort_browser_profiles(
                         &mut visible_and_hidden_profiles.visible_browser_profiles,
                         profile_order,
+                        config.get_ui_config().profile_sort,
                     );
 
                     let ui_browsers = UI::real_to_ui_browsers(
@@ -920,6 +1497,7 @@ pub fn handle_messages_to_main(
                 let new_default_profile = default_opener.map(|p| ProfileAndOptions {
                     profile: p.profile,
                     incognito: p.incognito,
+                    app_window: p.app_window,
                 });
 
                 let mut config = app_finder.load_config();
@@ -937,21 +1515,56 @@ pub fn handle_messages_to_main(
                     show_hotkeys: settings.show_hotkeys,
                     quit_on_lost_focus: settings.quit_on_lost_focus,
                     theme: settings.theme,
+                    profile_sort: settings.profile_sort,
                 };
 
                 let mut config = app_finder.load_config();
+                let profile_sort_changed =
+                    config.get_ui_config().profile_sort != ui_config.profile_sort;
                 config.set_ui_config(ui_config);
                 app_finder.save_config(&config);
+
+                if profile_sort_changed {
+                    // regenerate to get back the natural ordering before sorting again
+                    *visible_and_hidden_profiles =
+                        generate_all_browser_profiles(&config, &app_finder, false);
+
+                    let ui_browsers = UI::real_to_ui_browsers(
+                        &visible_and_hidden_profiles.visible_browser_profiles,
+                    );
+                    ui_event_sink
+                        .submit_command(ui::NEW_BROWSERS_RECEIVED, ui_browsers, Target::Global)
+                        .ok();
+                }
             }
             MessageToMain::SaveConfigUIBehavioralSettings(settings) => {
                 info!("Saving Behavioral settings");
                 let behavioral_config = BehavioralConfig {
                     unwrap_urls: settings.unwrap_urls,
+                    canonicalize_amp_urls: settings.canonicalize_amp_urls,
+                    canonicalize_mobile_urls: settings.canonicalize_mobile_urls,
+                    mobile_hosts: settings.mobile_hosts.to_vec(),
                 };
 
                 let mut config = app_finder.load_config();
+                let temporary_profiles_changed =
+                    config.get_temporary_profiles().enabled != settings.temporary_profiles_enabled;
                 config.set_behavior(behavioral_config);
+                config.set_temporary_profiles_enabled(settings.temporary_profiles_enabled);
                 app_finder.save_config(&config);
+
+                if temporary_profiles_changed {
+                    // temporary profiles are added (or left out) when generating the profiles
+                    *visible_and_hidden_profiles =
+                        generate_all_browser_profiles(&config, &app_finder, false);
+
+                    let ui_browsers = UI::real_to_ui_browsers(
+                        &visible_and_hidden_profiles.visible_browser_profiles,
+                    );
+                    ui_event_sink
+                        .submit_command(ui::NEW_BROWSERS_RECEIVED, ui_browsers, Target::Global)
+                        .ok();
+                }
             }
         }
     }
@@ -998,11 +1611,12 @@ pub fn open_link_if_matching_rule(
         let profile_and_options = opening_profile_id.clone();
         let profile_id = profile_and_options.profile;
         let incognito = profile_and_options.incognito;
+        let app_window = profile_and_options.app_window;
 
         let profile_maybe =
             visible_and_hidden_profiles.get_browser_profile_by_id(profile_id.as_str());
         if let Some(profile) = profile_maybe {
-            profile.open_link(url_open_context.cleaned_url.as_str(), incognito);
+            profile.open_link(url_open_context.cleaned_url.as_str(), incognito, app_window);
             return true;
         }
     }
@@ -1019,6 +1633,7 @@ fn map_as_profile_and_options(opener: &Option<UIProfileAndIncognito>) -> Option<
     return opener.as_ref().map(|p| ProfileAndOptions {
         profile: p.profile.clone(),
         incognito: p.incognito,
+        app_window: p.app_window,
     });
 }
 
@@ -1119,7 +1734,10 @@ pub enum MoveTo {
 #[derive(Debug)]
 pub enum MessageToMain {
     Refresh,
-    OpenLink(usize, bool, String),
+    // profile index, incognito mode, app window mode, url
+    OpenLink(usize, bool, bool, String),
+    // same as OpenLink, but the command is copied instead of run
+    CopyLaunchCommand(usize, bool, bool, String),
     // UrlOpenRequest is almost like LinkOpenedFromBundle, but triggers gui, not from gui
     UrlOpenRequest(String, String),
     UrlPassedToMain(String, String, BehavioralConfig),
@@ -1134,3 +1752,467 @@ pub enum MessageToMain {
     SaveConfigUISettings(UIVisualSettings),
     SaveConfigUIBehavioralSettings(UIBehavioralSettings),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX_TOML: &str = r#"
+        [[apps]]
+        id = "firefox"
+        config_dir_relative = "firefox"
+        kind = "FIREFOX"
+        os = "LINUX"
+        "#;
+
+    const CHROME_TOML: &str = r#"
+        [[apps]]
+        id = "google-chrome"
+        config_dir_relative = "google-chrome"
+        kind = "CHROMIUM"
+        os = "LINUX"
+        "#;
+
+    const THUNDERBIRD_TOML: &str = r#"
+        [[apps]]
+        id = "thunderbird"
+        config_dir_relative = ".thunderbird"
+        kind = "THUNDERBIRD"
+        os = "LINUX"
+        "#;
+
+    const URL: &str = "https://example.com/?a=b&c=d";
+
+    fn to_strings(args: &[&str]) -> Vec<String> {
+        return args.iter().map(|arg| arg.to_string()).collect();
+    }
+
+    fn create_app(repository_toml: &str) -> SupportedApp {
+        return SupportedAppRepository::create_app_from_toml(repository_toml);
+    }
+
+    fn firefox(command: &[&str], bundle: &str) -> BrowserCommon {
+        return app(FIREFOX_TOML, "Firefox", command, bundle);
+    }
+
+    fn app(
+        repository_toml: &str,
+        display_name: &str,
+        command: &[&str],
+        bundle: &str,
+    ) -> BrowserCommon {
+        return BrowserCommon {
+            command: to_strings(command),
+            executable_path: command[0].to_string(),
+            bundle: bundle.to_string(),
+            display_name: display_name.to_string(),
+            icon_path: "".to_string(),
+            supported_app: create_app(repository_toml),
+            profiles_type: InstalledAppProfilesType::RealProfiles,
+            custom_launcher: None,
+            flatpak_app_id: None,
+            snap_name: None,
+            user_data_dir: None,
+            private_window_command: None,
+            new_window_command: None,
+            desktop_entry_icon: Some("firefox".to_string()),
+            desktop_file_path: None,
+            temporary_profiles: TemporaryProfilesConfig::default(),
+        };
+    }
+
+    fn profile(app: BrowserCommon, profile_is_temporary: bool) -> CommonBrowserProfile {
+        let installed_browser_profile = InstalledBrowserProfile {
+            profile_cli_arg_value: "default-release".to_string(),
+            profile_cli_container_name: None,
+            profile_name: "Personal".to_string(),
+            profile_icon: None,
+            profile_restricted_url_patterns: vec![],
+            locked_to_other_install: false,
+            profile_container_color: None,
+            profile_container_icon: None,
+            profile_account_email: None,
+            profile_account_domain: None,
+            profile_last_used: None,
+            profile_is_browser_default: false,
+            profile_dir: None,
+            profile_launch_by_dir: false,
+            profile_is_temporary: profile_is_temporary,
+        };
+        return CommonBrowserProfile::new(&installed_browser_profile, Arc::new(app));
+    }
+
+    // profile args as when opening a link, but without creating a temporary profile
+    fn launch_plan(p: &CommonBrowserProfile, incognito_mode: bool, os: AppOS) -> LaunchPlan {
+        let profile_args = p.get_browser_common().get_profile_args(p, true);
+        return p.get_browser_common().to_launch_plan(
+            p,
+            URL,
+            profile_args,
+            incognito_mode,
+            false,
+            os,
+        );
+    }
+
+    #[test]
+    fn test_launch_plan_linux() {
+        let p = profile(firefox(&["/usr/lib/firefox/firefox", "%u"], ""), false);
+        assert_eq!(
+            launch_plan(&p, false, AppOS::LINUX),
+            LaunchPlan {
+                program: "/usr/lib/firefox/firefox".to_string(),
+                args: to_strings(&[URL, "-P", "default-release"]),
+                env: vec![],
+                cwd: None,
+                detach: DetachStrategy::Spawn,
+            }
+        );
+
+        // private window action of the snap keeps the incognito argument after the program
+        let snap_command = [
+            "env",
+            "BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/firefox_firefox.desktop",
+            "/snap/bin/firefox",
+        ];
+        let mut snap_firefox = firefox(&[&snap_command[..], &["%u"]].concat(), "");
+        snap_firefox.private_window_command = Some(to_strings(
+            &[&snap_command[..], &["--private-window", "%u"]].concat(),
+        ));
+        let p = profile(snap_firefox, false);
+        let plan = launch_plan(&p, true, AppOS::LINUX);
+        assert_eq!(plan.program, "/snap/bin/firefox");
+        assert_eq!(
+            plan.args,
+            to_strings(&["--private-window", URL, "-P", "default-release"])
+        );
+        assert_eq!(plan.env[0].0, "BAMF_DESKTOP_FILE_HINT");
+
+        let mut flatpak_firefox = firefox(
+            &[
+                "/usr/bin/flatpak",
+                "run",
+                "--command=firefox",
+                "--file-forwarding",
+                "org.mozilla.firefox",
+                "@@u",
+                "%u",
+                "@@",
+            ],
+            "",
+        );
+        flatpak_firefox.flatpak_app_id = Some("org.mozilla.firefox".to_string());
+        let p = profile(flatpak_firefox, false);
+        assert_eq!(
+            launch_plan(&p, true, AppOS::LINUX).args,
+            to_strings(&[
+                "run",
+                "--command=firefox",
+                "--file-forwarding",
+                "org.mozilla.firefox",
+                "--private-window",
+                "-P",
+                "default-release",
+                "@@u",
+                URL,
+                "@@"
+            ])
+        );
+
+        let p = profile(firefox(&["/usr/lib/firefox/firefox", "%u"], ""), true);
+        assert_eq!(
+            launch_plan(&p, false, AppOS::LINUX).detach,
+            DetachStrategy::SpawnAndWait
+        );
+    }
+
+    #[test]
+    fn test_launch_plan_new_window_action() {
+        let generic_toml = r#"
+            [[apps]]
+            id = "midori"
+            kind = "GENERIC"
+            os = "LINUX"
+            "#;
+        let mut midori = app(generic_toml, "Midori", &["/usr/bin/midori", "%u"], "");
+        midori.new_window_command = Some(to_strings(&["/usr/bin/midori", "--new-window", "%u"]));
+        let p = profile(midori, false);
+        assert_eq!(
+            launch_plan(&p, false, AppOS::LINUX).args,
+            to_strings(&["--new-window", URL])
+        );
+
+        // apps with repository arguments keep their own command
+        let mut linux_firefox = firefox(&["/usr/lib/firefox/firefox", "%u"], "");
+        linux_firefox.new_window_command =
+            Some(to_strings(&["/usr/lib/firefox/firefox", "--new-window", "%u"]));
+        let p = profile(linux_firefox, false);
+        assert_eq!(
+            launch_plan(&p, false, AppOS::LINUX).args,
+            to_strings(&[URL, "-P", "default-release"])
+        );
+    }
+
+    #[test]
+    fn test_find_profile_args_index() {
+        // after the .desktop file arguments, including the url
+        assert_eq!(find_profile_args_index(&to_strings(&["--new-window", "%u"])), 2);
+        assert_eq!(find_profile_args_index(&[]), 0);
+        // qutebrowser takes every argument after `--untrusted-args` as a url
+        assert_eq!(
+            find_profile_args_index(&to_strings(&["--untrusted-args", "%u"])),
+            0
+        );
+        assert_eq!(
+            find_profile_args_index(&to_strings(&["--target", "window", "--untrusted-args", "%u"])),
+            2
+        );
+    }
+
+    #[test]
+    fn test_unique_app_id_of_user_data_dir_instance() {
+        let command = ["/usr/bin/google-chrome-stable", "%U"];
+        let chrome = app(CHROME_TOML, "Google Chrome", &command, "google-chrome");
+        let mut chrome_instance = chrome.clone();
+        chrome_instance.user_data_dir = Some("/home/user/chrome-a".to_string());
+
+        assert_eq!(chrome.get_unique_app_id(), "/usr/bin/google-chrome-stable");
+        assert_eq!(
+            chrome_instance.get_unique_app_id(),
+            "/usr/bin/google-chrome-stable --user-data-dir=/home/user/chrome-a"
+        );
+        // the instance runs the same executable, with its own user data dir
+        let p = profile(chrome_instance, false);
+        let plan = launch_plan(&p, false, AppOS::LINUX);
+        assert_eq!(plan.program, "/usr/bin/google-chrome-stable");
+    }
+
+    #[test]
+    fn test_launch_plan_mail_client() {
+        let mailto_url = "mailto:a@example.com?subject=Don't%20panic";
+        let command = ["/usr/bin/thunderbird", "%u"];
+        let p = profile(app(THUNDERBIRD_TOML, "Thunderbird", &command, ""), false);
+        let thunderbird_launch_plan = |url: &str, os: AppOS| {
+            let profile_args = p.get_browser_common().get_profile_args(&p, true);
+            return p
+                .get_browser_common()
+                .to_launch_plan(&p, url, profile_args, false, false, os);
+        };
+
+        // `-compose` takes the mailto url as is
+        assert_eq!(
+            thunderbird_launch_plan(mailto_url, AppOS::LINUX).args,
+            to_strings(&["-compose", mailto_url, "-P", "default-release"])
+        );
+        assert_eq!(
+            thunderbird_launch_plan(mailto_url, AppOS::WINDOWS).args,
+            to_strings(&["-P", "default-release", "-compose", mailto_url])
+        );
+        assert_eq!(
+            thunderbird_launch_plan(URL, AppOS::LINUX).args,
+            to_strings(&[URL, "-P", "default-release"])
+        );
+    }
+
+    #[test]
+    fn test_supports_temporary_profile() {
+        assert!(firefox(&["/usr/lib/firefox/firefox", "%u"], "").supports_temporary_profile());
+
+        // sandboxed apps can't access the temporary profiles dir
+        let mut snap_firefox = firefox(&["/snap/bin/firefox", "%u"], "");
+        snap_firefox.snap_name = Some("firefox".to_string());
+        assert!(!snap_firefox.supports_temporary_profile());
+
+        let mut flatpak_firefox = firefox(&["/usr/bin/flatpak", "run", "org.mozilla.firefox"], "");
+        flatpak_firefox.flatpak_app_id = Some("org.mozilla.firefox".to_string());
+        assert!(!flatpak_firefox.supports_temporary_profile());
+    }
+
+    #[test]
+    fn test_sort_most_recently_used_within_each_browser() {
+        let firefox_profile = |name: &str, last_used: Option<u64>| {
+            let mut p = profile(firefox(&["/usr/bin/firefox"], ""), false);
+            p.profile_name = name.to_string();
+            p.profile_last_used = last_used;
+            return p;
+        };
+        let nightly_profile = |name: &str, last_used: Option<u64>| {
+            let mut p = profile(firefox(&["/usr/bin/firefox-nightly"], ""), false);
+            p.profile_name = name.to_string();
+            p.profile_last_used = last_used;
+            return p;
+        };
+
+        let mut profiles = vec![
+            firefox_profile("a", Some(10)),
+            firefox_profile("b", None),
+            firefox_profile("c", Some(20)),
+            nightly_profile("d", Some(5)),
+            nightly_profile("e", Some(30)),
+        ];
+        sort_browser_profiles(&mut profiles, &vec![], ProfileSortMode::MostRecentlyUsed);
+
+        let names: Vec<&str> = profiles.iter().map(|p| p.profile_name.as_str()).collect();
+        assert_eq!(names, vec!["c", "a", "b", "e", "d"]);
+    }
+
+    #[test]
+    fn test_launch_plan_macos() {
+        let mac_firefox = firefox(
+            &["/Applications/Firefox.app/Contents/MacOS/firefox"],
+            "org.mozilla.firefox",
+        );
+        let p = profile(mac_firefox.clone(), false);
+        assert_eq!(
+            launch_plan(&p, true, AppOS::MAC),
+            LaunchPlan {
+                program: "open".to_string(),
+                args: to_strings(&[
+                    "-b",
+                    "org.mozilla.firefox",
+                    "-n",
+                    "--args",
+                    "-P",
+                    "default-release",
+                    "--private-window",
+                    URL
+                ]),
+                env: vec![],
+                cwd: None,
+                detach: DetachStrategy::LaunchServices,
+            }
+        );
+
+        // `open` exits right away, so it isn't waited for even with a temporary profile
+        let p = profile(mac_firefox, true);
+        assert_eq!(
+            launch_plan(&p, false, AppOS::MAC).detach,
+            DetachStrategy::LaunchServices
+        );
+    }
+
+    #[test]
+    fn test_launch_plan_windows() {
+        let firefox_exe = r"C:\Program Files\Mozilla Firefox\firefox.exe";
+        let p = profile(firefox(&[firefox_exe], "firefox"), false);
+        let plan = launch_plan(&p, true, AppOS::WINDOWS);
+        assert_eq!(
+            plan,
+            LaunchPlan {
+                program: firefox_exe.to_string(),
+                args: to_strings(&["-P", "default-release", "--private-window", URL]),
+                env: vec![],
+                cwd: None,
+                detach: DetachStrategy::Spawn,
+            }
+        );
+        assert_eq!(
+            plan.to_command_line(AppOS::WINDOWS),
+            "\"C:\\Program Files\\Mozilla Firefox\\firefox.exe\" -P default-release --private-window \
+             \"https://example.com/?a=b&c=d\""
+        );
+    }
+
+    #[test]
+    fn test_launch_plan_profile_dir() {
+        let profile_dir =
+            std::env::temp_dir().join("browsers-launch-plan-test-not-running-profile");
+        let mut p = profile(firefox(&["/usr/lib/firefox/firefox", "%u"], ""), false);
+        p.profile_dir = Some(profile_dir.to_string_lossy().to_string());
+        p.profile_launch_by_dir = true;
+
+        // the shown command doesn't check the profile lock
+        assert_eq!(
+            launch_plan(&p, false, AppOS::LINUX).args,
+            to_strings(&[URL, "--profile", profile_dir.to_string_lossy().as_ref()])
+        );
+
+        // a profile which is not running is started as a separate instance
+        assert_eq!(
+            p.get_browser_common().get_profile_args(&p, false),
+            to_strings(&[
+                "--profile",
+                profile_dir.to_string_lossy().as_ref(),
+                "--new-instance"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_launch_plan_user_data_dir() {
+        let command = ["/usr/bin/google-chrome-stable", "%U"];
+        let mut chrome = app(CHROME_TOML, "Google Chrome", &command, "google-chrome");
+        chrome.user_data_dir = Some("/home/user/chrome-a".to_string());
+        let mut p = profile(chrome, false);
+        p.profile_cli_arg_value = "Profile 1".to_string();
+
+        assert_eq!(
+            launch_plan(&p, false, AppOS::LINUX).args,
+            to_strings(&[
+                URL,
+                "--user-data-dir=/home/user/chrome-a",
+                "--profile-directory=Profile 1"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_launch_plan_temporary_profile() {
+        let p = profile(firefox(&["/usr/lib/firefox/firefox", "%u"], ""), true);
+        let plan = launch_plan(&p, false, AppOS::LINUX);
+
+        assert_eq!(plan.args[0], URL);
+        assert_eq!(plan.args[1], "--profile");
+        assert_eq!(plan.args[3], "--new-instance");
+        // a dry run doesn't create the temporary profile
+        assert!(!Path::new(plan.args[2].as_str()).exists());
+        assert_eq!(plan.detach, DetachStrategy::SpawnAndWait);
+    }
+
+    #[test]
+    fn test_launch_plan_app_window() {
+        let command = ["/usr/bin/google-chrome-stable", "%U"];
+        let mut p = profile(
+            app(CHROME_TOML, "Google Chrome", &command, "com.google.Chrome"),
+            false,
+        );
+        p.profile_cli_arg_value = "Default".to_string();
+        let app_window_launch_plan = |os: AppOS| {
+            let profile_args = p.get_browser_common().get_profile_args(&p, true);
+            return p
+                .get_browser_common()
+                .to_launch_plan(&p, URL, profile_args, true, true, os);
+        };
+        let app_window_arg = format!("--app={}", URL);
+
+        // the url is replaced by the app window argument, so it's where the url would be
+        assert_eq!(
+            app_window_launch_plan(AppOS::LINUX).args,
+            to_strings(&[
+                "--incognito",
+                app_window_arg.as_str(),
+                "--profile-directory=Default"
+            ])
+        );
+        assert_eq!(
+            app_window_launch_plan(AppOS::MAC).args,
+            to_strings(&[
+                "-b",
+                "com.google.Chrome",
+                "-n",
+                "--args",
+                "--profile-directory=Default",
+                "--incognito",
+                app_window_arg.as_str()
+            ])
+        );
+        assert_eq!(
+            app_window_launch_plan(AppOS::WINDOWS).args,
+            to_strings(&[
+                "--profile-directory=Default",
+                "--incognito",
+                app_window_arg.as_str()
+            ])
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::{BTreeMap, HashSet};
+use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
@@ -15,6 +16,16 @@ use crate::{InstalledBrowser, SupportedAppRepository};
 
 const XDG_NAME: &'static str = "software.Browsers";
 
+// `[Desktop Action <name>]` ids, lowercase and without dashes,
+// e.g "new-private-window" (Firefox, Chrome), "NewPrivateWindow" (Chromium)
+const PRIVATE_WINDOW_ACTIONS: [&str; 4] = [
+    "newprivatewindow",
+    "privatewindow",
+    "newincognitowindow",
+    "incognito",
+];
+const NEW_WINDOW_ACTIONS: [&str; 1] = ["newwindow"];
+
 #[derive(Clone)]
 struct DesktopEntryHolder {
     app_id: String,
@@ -23,6 +34,15 @@ struct DesktopEntryHolder {
 
     // uses %u or %U, see https://specifications.freedesktop.org/desktop-entry-spec/latest/ar01s07.html
     exec: String,
+
+    // Exec of the private window and new window desktop actions
+    private_window_exec: Option<String>,
+    new_window_exec: Option<String>,
+
+    desktop_file_path: PathBuf,
+
+    // snapd adds X-SnapInstanceName to the .desktop files it generates
+    snap_instance_name: Option<String>,
 }
 
 pub struct OsHelper {
@@ -138,7 +158,11 @@ impl OsHelper {
                             .is_some();
 
                         if contains_mime_type {
-                            Self::freedesktop_desktop_entry_to_desktop_entry_holder(&entry, locales)
+                            Self::freedesktop_desktop_entry_to_desktop_entry_holder(
+                                &entry,
+                                desktop_file_path,
+                                locales,
+                            )
                         } else {
                             None
                         }
@@ -148,6 +172,7 @@ impl OsHelper {
 
     fn freedesktop_desktop_entry_to_desktop_entry_holder(
         desktop_entry: &DesktopEntry,
+        desktop_file_path: &Path,
         locales: &Vec<String>,
     ) -> Option<DesktopEntryHolder> {
         let app_id = desktop_entry.id();
@@ -167,12 +192,22 @@ impl OsHelper {
         };
 
         let icon_maybe = desktop_entry.icon().map(|icon| icon.to_string());
+        let private_window_exec_maybe =
+            find_desktop_action_exec(desktop_entry, &PRIVATE_WINDOW_ACTIONS);
+        let new_window_exec_maybe = find_desktop_action_exec(desktop_entry, &NEW_WINDOW_ACTIONS);
+        let snap_instance_name_maybe = desktop_entry
+            .desktop_entry("X-SnapInstanceName")
+            .map(|snap_instance_name| snap_instance_name.to_string());
 
         return Some(DesktopEntryHolder {
             app_id: app_id.to_string(),
             display_name: display_name,
             icon: icon_maybe,
             exec: exec.to_string(),
+            private_window_exec: private_window_exec_maybe,
+            new_window_exec: new_window_exec_maybe,
+            desktop_file_path: desktop_file_path.to_path_buf(),
+            snap_instance_name: snap_instance_name_maybe,
         });
     }
 
@@ -197,11 +232,14 @@ impl OsHelper {
             return None;
         }
 
-        // check if it's snap package in a bit naive way
         // "env BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/firefox_firefox.desktop /snap/bin/firefox %u"
-        let is_snap = command_parts
-            .iter()
-            .any(|part| part.starts_with("/snap/bin"));
+        // newer snaps don't refer to /snap/bin in Exec, so check the .desktop file as well
+        let snap_name_maybe = find_snap_name(desktop_entry_holder, &command_parts);
+        let is_snap = snap_name_maybe.is_some();
+
+        // "/usr/bin/flatpak run --branch=stable --arch=x86_64 --command=firefox --file-forwarding org.mozilla.firefox @@u %u @@"
+        let flatpak_app_id_maybe = find_flatpak_app_id(&command_parts);
+        let is_flatpak = flatpak_app_id_maybe.is_some();
 
         //let snap_root_path = self.snap_base.clone();
         //let snap_linux_config_dir_relative_path = PathBuf::from(snap_name)
@@ -222,11 +260,17 @@ impl OsHelper {
             .map(|path_perhaps| Path::new(path_perhaps))
             .unwrap_or(Path::new("unknown"));
 
-        // TODO: get correct path for firefox snap, which one is actually used to calculate installation id in profiles.ini
-        // let command_dir = executable_path.parent();
-        // let binary_dir = command_dir.and_then(|p| p.to_str()).unwrap_or("").to_string();
-
-        // env BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/firefox_firefox.desktop
+        // binary which Firefox-based browsers use to calculate installation id in profiles.ini;
+        // it's the real binary as seen by the running browser, not the launcher in Exec
+        let binary_path = if let Some(snap_name) = &snap_name_maybe {
+            // snap binaries are wrappers in /snap/bin, the real one is inside the snap
+            snap_binary_path(snap_name.as_str())
+        } else if let Some(flatpak_app_id) = &flatpak_app_id_maybe {
+            flatpak_binary_path(flatpak_app_id.as_str(), &command_parts)
+        } else {
+            // e.g /usr/bin/firefox -> /usr/lib/firefox/firefox
+            resolve_binary_path(executable_path_best_guess)
+        };
 
         //let name = app_info.name().to_string();
         /*let icon_maybe = app_info.icon();
@@ -242,20 +286,22 @@ impl OsHelper {
             create_icon_for_app(icon_str.as_str(), icon_path_str.as_str())
         }
 
+        let private_window_command_maybe = desktop_entry_holder
+            .private_window_exec
+            .as_ref()
+            .and_then(|exec| to_desktop_action_command(exec.as_str()));
+        let new_window_command_maybe = desktop_entry_holder
+            .new_window_exec
+            .as_ref()
+            .and_then(|exec| to_desktop_action_command(exec.as_str()));
+
         let display_name = desktop_entry_holder.display_name.as_str();
         //let _string = app_info.to_string();
         //println!("app_info: {}", id);
 
-        // new firefox actually doesn't refer to the snap binary in the desktop file,
-        // so there is no clean way to check that
-        // I think we should just try both
-        if !is_snap {
-            // look deeper!
-        }
-
-        let app_config_dir_abs = supported_app.get_app_config_dir_abs(is_snap, false);
+        let app_config_dir_abs = supported_app.get_app_config_dir_abs(is_snap, is_flatpak, false);
 
-        let profiles = supported_app.find_profiles(executable_path_best_guess, app_config_dir_abs);
+        let profiles = supported_app.find_profiles(binary_path.as_path(), app_config_dir_abs);
 
         let browser = InstalledBrowser {
             command: command_parts.clone(),
@@ -266,11 +312,203 @@ impl OsHelper {
             icon_path: icon_path_str.clone(),
             profiles: profiles,
             restricted_domains: restricted_domains,
+            flatpak_app_id: flatpak_app_id_maybe,
+            snap_name: snap_name_maybe,
+            user_data_dir: None,
+            private_window_command: private_window_command_maybe,
+            new_window_command: new_window_command_maybe,
+            desktop_entry_icon: desktop_entry_holder.icon.clone(),
+            desktop_file_path: Some(desktop_entry_holder.desktop_file_path.display().to_string()),
         };
         return Some(browser);
     }
 }
 
+// Exec of the first action listed in `Actions=` which is one of `action_ids`
+fn find_desktop_action_exec(desktop_entry: &DesktopEntry, action_ids: &[&str]) -> Option<String> {
+    let actions = desktop_entry.desktop_entry("Actions")?;
+    let action = find_desktop_action(actions, action_ids)?;
+    return desktop_entry
+        .action_exec(action)
+        .map(|exec| exec.to_string());
+}
+
+// "new-window;new-private-window;" -> "new-private-window"
+fn find_desktop_action<'a>(actions: &'a str, action_ids: &[&str]) -> Option<&'a str> {
+    return actions
+        .split(';')
+        .map(|action| action.trim())
+        .find(|action| {
+            let action_id = action.to_lowercase().replace(['-', '_'], "");
+            action_ids.contains(&action_id.as_str())
+        });
+}
+
+fn to_desktop_action_command(exec: &str) -> Option<Vec<String>> {
+    let command_parts = match shell_words::split(exec) {
+        Ok(command_parts) => command_parts,
+        Err(e) => {
+            warn!("Could not parse desktop action Exec {:?}: {}", exec, e);
+            return None;
+        }
+    };
+
+    if command_parts.is_empty() {
+        return None;
+    }
+
+    return Some(command_parts);
+}
+
+// Returns the snap name if the app is installed as a snap package
+fn find_snap_name(
+    desktop_entry_holder: &DesktopEntryHolder,
+    command_parts: &[String],
+) -> Option<String> {
+    return find_snap_instance_name(desktop_entry_holder, command_parts)
+        .map(|snap_instance_name| to_snap_name(snap_instance_name.as_str()));
+}
+
+// parallel installs of a snap are instances named "<snap>_<key>" (e.g "firefox_work"),
+// but inside the snap everything is still under the snap name
+fn to_snap_name(snap_instance_name: &str) -> String {
+    // desktop files of instances are named "<snap>+<key>_<app>.desktop"
+    return snap_instance_name
+        .split(['_', '+'])
+        .next()
+        .unwrap_or(snap_instance_name)
+        .to_string();
+}
+
+fn find_snap_instance_name(
+    desktop_entry_holder: &DesktopEntryHolder,
+    command_parts: &[String],
+) -> Option<String> {
+    if let Some(snap_instance_name) = &desktop_entry_holder.snap_instance_name {
+        return Some(snap_instance_name.to_string());
+    }
+
+    // "/snap/bin/firefox"
+    let snap_bin_name_maybe = command_parts
+        .iter()
+        .find_map(|part| part.strip_prefix("/snap/bin/"))
+        .map(|snap_bin| snap_bin.split('.').next().unwrap_or(snap_bin).to_string());
+    if snap_bin_name_maybe.is_some() {
+        return snap_bin_name_maybe;
+    }
+
+    // /var/lib/snapd/desktop/applications/<snap>_<app>.desktop
+    if desktop_entry_holder
+        .desktop_file_path
+        .starts_with("/var/lib/snapd/desktop")
+    {
+        let snap_name_maybe = desktop_entry_holder
+            .app_id
+            .split('_')
+            .next()
+            .map(|snap_name| snap_name.to_string());
+        return snap_name_maybe;
+    }
+
+    return None;
+}
+
+// Firefox-based snaps ship the real binary in /snap/<snap>/current/usr/lib/<snap>/<snap>
+// (e.g /snap/firefox/current/usr/lib/firefox/firefox)
+fn snap_binary_path(snap_name: &str) -> PathBuf {
+    return PathBuf::from("/snap")
+        .join(snap_name)
+        .join("current")
+        .join("usr")
+        .join("lib")
+        .join(snap_name)
+        .join(snap_name);
+}
+
+// Flatpak apps see their files under /app inside the sandbox,
+// e.g /app/lib/firefox/firefox for "flatpak run --command=firefox org.mozilla.firefox"
+fn flatpak_binary_path(flatpak_app_id: &str, command_parts: &[String]) -> PathBuf {
+    let command_name = find_flatpak_command(command_parts).unwrap_or_else(|| {
+        // "org.mozilla.firefox" -> "firefox"
+        let app_name = flatpak_app_id.rsplit('.').next().unwrap_or(flatpak_app_id);
+        app_name.to_lowercase()
+    });
+
+    return PathBuf::from("/app")
+        .join("lib")
+        .join(command_name.as_str())
+        .join(command_name.as_str());
+}
+
+// "--command=firefox" or "--command firefox"
+fn find_flatpak_command(command_parts: &[String]) -> Option<String> {
+    let mut parts = command_parts.iter();
+    while let Some(part) = parts.next() {
+        if let Some(command_name) = part.strip_prefix("--command=") {
+            return Some(command_name.to_string());
+        }
+        if part == "--command" {
+            return parts.next().map(|command_name| command_name.to_string());
+        }
+    }
+    return None;
+}
+
+// Finds the executable from PATH if needed and resolves symlinks
+fn resolve_binary_path(executable_path: &Path) -> PathBuf {
+    let executable_path_abs = if executable_path.is_absolute() {
+        Some(executable_path.to_path_buf())
+    } else {
+        env::var_os("PATH").and_then(|paths| {
+            env::split_paths(&paths)
+                .map(|dir| dir.join(executable_path))
+                .find(|candidate| candidate.is_file())
+        })
+    };
+
+    return executable_path_abs
+        .and_then(|path| fs::canonicalize(path).ok())
+        .unwrap_or_else(|| executable_path.to_path_buf());
+}
+
+// Returns the flatpak application id if command runs a flatpak app,
+// e.g "flatpak run --branch=stable --command=firefox org.mozilla.firefox @@u %u @@"
+fn find_flatpak_app_id(command_parts: &[String]) -> Option<String> {
+    // flatpak run options which take the value as a separate argument
+    const FLATPAK_OPTIONS_WITH_VALUE: [&str; 6] = [
+        "--arch",
+        "--branch",
+        "--command",
+        "--cwd",
+        "--runtime",
+        "--runtime-version",
+    ];
+
+    let mut parts = command_parts
+        .iter()
+        // skip "env VAR=value"
+        .skip_while(|part| part.as_str() == "env" || part.contains('='));
+
+    let program = parts.next()?;
+    if program != "flatpak" && !program.ends_with("/flatpak") {
+        return None;
+    }
+    if parts.next()? != "run" {
+        return None;
+    }
+
+    while let Some(part) = parts.next() {
+        if !part.starts_with('-') {
+            return Some(part.to_string());
+        }
+        if !part.contains('=') && FLATPAK_OPTIONS_WITH_VALUE.contains(&part.as_str()) {
+            parts.next();
+        }
+    }
+
+    return None;
+}
+
 fn create_icon_for_app(icon_str: &str, to_icon_path: &str) {
     let icon_path_maybe = find_icon_path_from_desktop_icon_value(icon_str);
     if icon_path_maybe.is_none() {
@@ -427,6 +665,13 @@ pub fn get_snap_root_dir() -> PathBuf {
     //return buf;
 }
 
+// $HOME/.var/app
+pub fn get_flatpak_root_dir() -> PathBuf {
+    // TODO: escape sandbox if Browsers is running in snap/flatpak
+    let home_dir = dirs::home_dir().unwrap();
+    return home_dir.join(".var").join("app");
+}
+
 pub fn linux_get_unsandboxed_home_dir() -> PathBuf {
     // TODO: escape sandbox if in snap/flatpak
     return dirs::home_dir().unwrap();
@@ -483,3 +728,223 @@ fn query_default_app(scheme: &str) -> Option<String> {
     info!("Default for {scheme} is '{default_app}'");
     return Some(default_app);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_dir;
+
+    fn to_parts(command_str: &str) -> Vec<String> {
+        return shell_words::split(command_str).unwrap();
+    }
+
+    #[test]
+    fn test_find_flatpak_app_id() {
+        assert_eq!(
+            find_flatpak_app_id(&to_parts(
+                "/usr/bin/flatpak run --branch=stable --arch=x86_64 --command=firefox --file-forwarding org.mozilla.firefox @@u %u @@"
+            )),
+            Some("org.mozilla.firefox".to_string())
+        );
+        assert_eq!(
+            find_flatpak_app_id(&to_parts(
+                "flatpak run --command chromium org.chromium.Chromium %U"
+            )),
+            Some("org.chromium.Chromium".to_string())
+        );
+        assert_eq!(
+            find_flatpak_app_id(&to_parts(
+                "env GDK_BACKEND=x11 /usr/bin/flatpak run com.brave.Browser"
+            )),
+            Some("com.brave.Browser".to_string())
+        );
+    }
+
+    #[test]
+    fn test_find_flatpak_app_id_not_flatpak() {
+        assert_eq!(find_flatpak_app_id(&to_parts("/usr/bin/firefox %u")), None);
+        assert_eq!(find_flatpak_app_id(&to_parts("/usr/bin/flatpak")), None);
+        assert_eq!(find_flatpak_app_id(&to_parts("flatpak install x")), None);
+    }
+
+    fn desktop_entry_holder(
+        app_id: &str,
+        desktop_file_path: &str,
+        snap_instance_name: Option<&str>,
+    ) -> DesktopEntryHolder {
+        return DesktopEntryHolder {
+            app_id: app_id.to_string(),
+            display_name: app_id.to_string(),
+            icon: None,
+            exec: "".to_string(),
+            private_window_exec: None,
+            new_window_exec: None,
+            desktop_file_path: PathBuf::from(desktop_file_path),
+            snap_instance_name: snap_instance_name.map(|name| name.to_string()),
+        };
+    }
+
+    #[test]
+    fn test_find_desktop_action() {
+        assert_eq!(
+            find_desktop_action("new-window;new-private-window;", &PRIVATE_WINDOW_ACTIONS),
+            Some("new-private-window")
+        );
+        assert_eq!(
+            find_desktop_action("new-window;new-private-window;", &NEW_WINDOW_ACTIONS),
+            Some("new-window")
+        );
+        assert_eq!(
+            find_desktop_action("NewWindow;NewPrivateWindow", &PRIVATE_WINDOW_ACTIONS),
+            Some("NewPrivateWindow")
+        );
+        assert_eq!(
+            find_desktop_action("new-window;new-incognito-window;", &PRIVATE_WINDOW_ACTIONS),
+            Some("new-incognito-window")
+        );
+        assert_eq!(
+            find_desktop_action("ComposeMessage;OpenAddressBook;", &PRIVATE_WINDOW_ACTIONS),
+            None
+        );
+        assert_eq!(find_desktop_action("", &NEW_WINDOW_ACTIONS), None);
+    }
+
+    #[test]
+    fn test_to_desktop_action_command() {
+        assert_eq!(
+            to_desktop_action_command("/usr/bin/google-chrome-stable --incognito"),
+            Some(vec![
+                "/usr/bin/google-chrome-stable".to_string(),
+                "--incognito".to_string()
+            ])
+        );
+        assert_eq!(
+            to_desktop_action_command(
+                "env BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/firefox_firefox.desktop /snap/bin/firefox --private-window %u"
+            ),
+            Some(to_parts(
+                "env BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/firefox_firefox.desktop /snap/bin/firefox --private-window %u"
+            ))
+        );
+        assert_eq!(to_desktop_action_command(""), None);
+        assert_eq!(to_desktop_action_command("firefox \"%u"), None);
+    }
+
+    #[test]
+    fn test_find_snap_name() {
+        let snap_desktop_file = "/var/lib/snapd/desktop/applications/firefox_firefox.desktop";
+
+        let with_instance_name =
+            desktop_entry_holder("firefox_firefox", snap_desktop_file, Some("firefox"));
+        assert_eq!(
+            find_snap_name(&with_instance_name, &to_parts("firefox %u")),
+            Some("firefox".to_string())
+        );
+
+        let holder =
+            desktop_entry_holder("firefox_firefox", "/usr/share/applications/x.desktop", None);
+        assert_eq!(
+            find_snap_name(
+                &holder,
+                &to_parts("env BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/firefox_firefox.desktop /snap/bin/firefox %u")
+            ),
+            Some("firefox".to_string())
+        );
+
+        let holder = desktop_entry_holder("chromium_chromium", snap_desktop_file, None);
+        assert_eq!(
+            find_snap_name(&holder, &to_parts("chromium %U")),
+            Some("chromium".to_string())
+        );
+    }
+
+    #[test]
+    fn test_find_snap_name_of_parallel_install() {
+        let snap_desktop_file = "/var/lib/snapd/desktop/applications/firefox+work_firefox.desktop";
+
+        let with_instance_name =
+            desktop_entry_holder("firefox+work_firefox", snap_desktop_file, Some("firefox_work"));
+        assert_eq!(
+            find_snap_name(&with_instance_name, &to_parts("/snap/bin/firefox_work %u")),
+            Some("firefox".to_string())
+        );
+
+        let holder = desktop_entry_holder("firefox+work_firefox", snap_desktop_file, None);
+        assert_eq!(
+            find_snap_name(&holder, &to_parts("/snap/bin/firefox_work %u")),
+            Some("firefox".to_string())
+        );
+        assert_eq!(
+            find_snap_name(&holder, &to_parts("firefox %u")),
+            Some("firefox".to_string())
+        );
+    }
+
+    #[test]
+    fn test_find_snap_name_not_snap() {
+        let holder =
+            desktop_entry_holder("firefox", "/usr/share/applications/firefox.desktop", None);
+        assert_eq!(
+            find_snap_name(&holder, &to_parts("/usr/lib/firefox/firefox %u")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_flatpak_binary_path() {
+        assert_eq!(
+            flatpak_binary_path(
+                "org.mozilla.firefox",
+                &to_parts("/usr/bin/flatpak run --branch=stable --arch=x86_64 --command=firefox --file-forwarding org.mozilla.firefox @@u %u @@")
+            ),
+            PathBuf::from("/app/lib/firefox/firefox")
+        );
+        assert_eq!(
+            flatpak_binary_path(
+                "io.gitlab.librewolf-community",
+                &to_parts("flatpak run --command librewolf io.gitlab.librewolf-community %u")
+            ),
+            PathBuf::from("/app/lib/librewolf/librewolf")
+        );
+        assert_eq!(
+            flatpak_binary_path("one.ablaze.floorp", &to_parts("flatpak run one.ablaze.floorp")),
+            PathBuf::from("/app/lib/floorp/floorp")
+        );
+    }
+
+    #[test]
+    fn test_resolve_binary_path() {
+        // e.g /usr/bin/firefox -> /usr/lib/firefox/firefox
+        let test_dir = create_test_dir("linux-utils", "binary-path");
+        let binary_path = test_dir.join("lib/firefox/firefox");
+        let launcher_path = test_dir.join("bin/firefox");
+        fs::create_dir_all(binary_path.parent().unwrap()).unwrap();
+        fs::create_dir_all(launcher_path.parent().unwrap()).unwrap();
+        fs::write(binary_path.as_path(), "").unwrap();
+        std::os::unix::fs::symlink(binary_path.as_path(), launcher_path.as_path()).unwrap();
+
+        assert_eq!(
+            resolve_binary_path(launcher_path.as_path()),
+            fs::canonicalize(binary_path.as_path()).unwrap()
+        );
+        // the real binary (or a wrapper script) stays as it is
+        assert_eq!(
+            resolve_binary_path(binary_path.as_path()),
+            fs::canonicalize(binary_path.as_path()).unwrap()
+        );
+        assert_eq!(
+            resolve_binary_path(Path::new("/nonexistent/firefox")),
+            PathBuf::from("/nonexistent/firefox")
+        );
+        // found from PATH
+        assert!(resolve_binary_path(Path::new("sh")).is_absolute());
+    }
+
+    #[test]
+    fn test_snap_binary_path() {
+        assert_eq!(
+            snap_binary_path("firefox"),
+            PathBuf::from("/snap/firefox/current/usr/lib/firefox/firefox")
+        );
+    }
+}
@@ -157,7 +157,7 @@ impl OsHelper {
         // TODO: check if "com.apple.security.app-sandbox" entitlement exists for the app
         // TODO: https://stackoverflow.com/questions/12177948/how-do-i-detect-if-my-app-is-sandboxed
         let is_macos_sandbox = has_sandbox_entitlement(bundle_path.as_str());
-        let app_config_dir_abs = supported_app.get_app_config_dir_abs(false, is_macos_sandbox);
+        let app_config_dir_abs = supported_app.get_app_config_dir_abs(false, false, is_macos_sandbox);
 
         let browser = InstalledBrowser {
             command: command_parts,
@@ -168,6 +168,13 @@ impl OsHelper {
             icon_path: icon_path_str.clone(),
             profiles: supported_app.find_profiles(executable_path.as_path(), app_config_dir_abs),
             restricted_domains: restricted_domain_patterns,
+            flatpak_app_id: None,
+            snap_name: None,
+            user_data_dir: None,
+            private_window_command: None,
+            new_window_command: None,
+            desktop_entry_icon: None,
+            desktop_file_path: None,
         };
 
         return Some(browser);
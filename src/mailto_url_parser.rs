@@ -0,0 +1,175 @@
+use url::Url;
+
+// mailto:<addresses>?<header>=<value>&<header>=<value>, see RFC 6068
+#[derive(Debug, Default, PartialEq)]
+pub struct MailtoUrl {
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+}
+
+// Addresses and header values are percent-decoded only after splitting,
+// so "%2C", "%26" and "%3D" stay part of the address or value.
+// Headers other than to, cc, bcc, subject and body are ignored (e.g in-reply-to).
+pub fn parse_mailto_url(url_str: &str) -> Option<MailtoUrl> {
+    let url_str = url_str.trim();
+    let scheme_end_index = url_str.find(':')?;
+    if !url_str[..scheme_end_index].eq_ignore_ascii_case("mailto") {
+        return None;
+    }
+    let after_scheme = &url_str[scheme_end_index + 1..];
+    // fragments are not part of mailto urls
+    let after_scheme = after_scheme
+        .split_once('#')
+        .map_or(after_scheme, |(before_fragment, _)| before_fragment);
+    let (addresses, headers) = after_scheme.split_once('?').unwrap_or((after_scheme, ""));
+
+    let mut mailto_url = MailtoUrl {
+        to: split_addresses(percent_decode(addresses).as_str()),
+        ..Default::default()
+    };
+
+    for header in headers.split('&').filter(|header| !header.is_empty()) {
+        let (name, value) = header.split_once('=').unwrap_or((header, ""));
+        let value = percent_decode(value);
+
+        match percent_decode(name).to_lowercase().as_str() {
+            "to" => mailto_url.to.extend(split_addresses(value.as_str())),
+            "cc" => mailto_url.cc.extend(split_addresses(value.as_str())),
+            "bcc" => mailto_url.bcc.extend(split_addresses(value.as_str())),
+            "subject" => mailto_url.subject = Some(value),
+            "body" => mailto_url.body = Some(value),
+            _ => {}
+        }
+    }
+
+    return Some(mailto_url);
+}
+
+// "you@Acme.com" -> "acme.com"; used as the host of mailto urls in rules
+pub fn get_recipient_domain(url: &Url) -> Option<String> {
+    let mailto_url = parse_mailto_url(url.as_str())?;
+    let first_recipient = mailto_url.to.first()?;
+    let (_, domain) = first_recipient.rsplit_once('@')?;
+    return Some(domain.trim_end_matches('>').to_lowercase());
+}
+
+fn split_addresses(addresses: &str) -> Vec<String> {
+    return addresses
+        .split(',')
+        .map(|address| address.trim().to_string())
+        .filter(|address| !address.is_empty())
+        .collect();
+}
+
+// "+" stays as is, unlike in form urlencoding
+fn percent_decode(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex_maybe = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex_maybe {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    return String::from_utf8_lossy(&decoded).to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mailto_url() {
+        assert_eq!(
+            parse_mailto_url(
+                "mailto:a@example.com,b@example.com?cc=c@example.com&subject=Hello%20there&body=1+1%3D2"
+            ),
+            Some(MailtoUrl {
+                to: vec!["a@example.com".to_string(), "b@example.com".to_string()],
+                cc: vec!["c@example.com".to_string()],
+                bcc: vec![],
+                subject: Some("Hello there".to_string()),
+                body: Some("1+1=2".to_string()),
+            })
+        );
+        assert_eq!(parse_mailto_url("https://example.com"), None);
+    }
+
+    // examples from RFC 6068, section 6
+    #[test]
+    fn test_parse_rfc_6068_examples() {
+        let mailto_url =
+            parse_mailto_url("mailto:infobot@example.com?subject=current-issue").unwrap();
+        assert_eq!(mailto_url.to, vec!["infobot@example.com"]);
+        assert_eq!(mailto_url.subject, Some("current-issue".to_string()));
+
+        let mailto_url = parse_mailto_url(
+            "mailto:list@example.org?In-Reply-To=%3C3469A91.D10AF4C@example.com%3E",
+        )
+        .unwrap();
+        assert_eq!(mailto_url.to, vec!["list@example.org"]);
+        assert_eq!(mailto_url.subject, None);
+
+        let mailto_url =
+            parse_mailto_url("mailto:?to=joe@example.com&cc=bob@example.com&body=hello").unwrap();
+        assert_eq!(mailto_url.to, vec!["joe@example.com"]);
+        assert_eq!(mailto_url.cc, vec!["bob@example.com"]);
+        assert_eq!(mailto_url.body, Some("hello".to_string()));
+
+        let mailto_url = parse_mailto_url(
+            "mailto:joe@example.com?cc=bob@example.com&body=hello%20there%0D%0Ajoe",
+        )
+        .unwrap();
+        assert_eq!(mailto_url.body, Some("hello there\r\njoe".to_string()));
+
+        let mailto_url = parse_mailto_url("mailto:gorby%25kremvax@example.com").unwrap();
+        assert_eq!(mailto_url.to, vec!["gorby%kremvax@example.com"]);
+
+        let mailto_url = parse_mailto_url("mailto:Mike%26family@example.org").unwrap();
+        assert_eq!(mailto_url.to, vec!["Mike&family@example.org"]);
+
+        let mailto_url = parse_mailto_url("mailto:%22not%40me%22@example.org").unwrap();
+        assert_eq!(mailto_url.to, vec!["\"not@me\"@example.org"]);
+
+        let mailto_url = parse_mailto_url("mailto:user@example.org?subject=caf%C3%A9").unwrap();
+        assert_eq!(mailto_url.subject, Some("caf\u{e9}".to_string()));
+
+        let mailto_url = parse_mailto_url(
+            "mailto:addr1@an.example,addr2@an.example?To=addr3@an.example&BODY=send%20current-issue",
+        )
+        .unwrap();
+        assert_eq!(
+            mailto_url.to,
+            vec!["addr1@an.example", "addr2@an.example", "addr3@an.example"]
+        );
+        assert_eq!(mailto_url.body, Some("send current-issue".to_string()));
+    }
+
+    #[test]
+    fn test_get_recipient_domain() {
+        let url = Url::parse("mailto:You@Acme.com?subject=hi").unwrap();
+        assert_eq!(get_recipient_domain(&url), Some("acme.com".to_string()));
+
+        let url = Url::parse("mailto:?to=bob%40example.org").unwrap();
+        assert_eq!(get_recipient_domain(&url), Some("example.org".to_string()));
+
+        let url = Url::parse("mailto:?subject=hi").unwrap();
+        assert_eq!(get_recipient_domain(&url), None);
+    }
+}
@@ -13,7 +13,7 @@ use tracing_subscriber::fmt::writer::MakeWriterExt;
 use browsers::utils::OSAppFinder;
 use browsers::{
     MessageToMain, UrlOpenContext, generate_all_browser_profiles, get_opening_rules,
-    open_link_if_matching_rule, prepare_ui, unwrap_url, utils,
+    open_link_if_matching_rule, prepare_ui, remove_unused_temporary_profiles, unwrap_url, utils,
 };
 use browsers::{handle_messages_to_main, paths};
 
@@ -54,11 +54,15 @@ fn main() {
     info!("Starting Browsers");
     info!("Logging to {}", log_file_path.display());
 
+    thread::spawn(remove_unused_temporary_profiles);
+
     let args: Vec<String> = env::args().collect();
     //info!("{:?}", args);
 
     let mut url = "".to_string();
-    let url_input_maybe = args.iter().find(|i| i.starts_with("http"));
+    let url_input_maybe = args
+        .iter()
+        .find(|i| i.starts_with("http") || i.starts_with("mailto:"));
     if let Some(url_input) = url_input_maybe {
         url = url_input.to_string();
     }
@@ -106,7 +110,7 @@ fn main() {
     );
 
     if !show_gui {
-        ui.print_visible_options();
+        ui.print_visible_options(&visible_and_hidden_profiles);
         return;
     }
 
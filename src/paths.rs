@@ -35,6 +35,11 @@ pub fn get_repository_toml_path() -> PathBuf {
     return get_repository_basedir().join("application-repository.toml");
 }
 
+// user's own additions/overrides to the bundled application repository
+pub fn get_user_repository_toml_path() -> PathBuf {
+    return get_config_root_dir().join("application-repository.toml");
+}
+
 pub fn get_config_json_path() -> PathBuf {
     return get_config_root_dir().join("config.json");
 }
@@ -119,6 +124,21 @@ pub fn get_snap_root() -> PathBuf {
     return PathBuf::new();
 }
 
+#[cfg(target_os = "macos")]
+pub fn get_flatpak_root() -> PathBuf {
+    return PathBuf::new();
+}
+
+#[cfg(target_os = "linux")]
+pub fn get_flatpak_root() -> PathBuf {
+    return linux_utils::get_flatpak_root_dir();
+}
+
+#[cfg(target_os = "windows")]
+pub fn get_flatpak_root() -> PathBuf {
+    return PathBuf::new();
+}
+
 pub fn get_app_icon_path() -> PathBuf {
     return get_resources_basedir().join("icons/512x512/software.Browsers.png");
 }
@@ -0,0 +1,230 @@
+use std::fs;
+use std::path::Path;
+
+use tracing::debug;
+
+// Firefox locks the profile directory while it's running:
+//   Linux:   `lock` symlink pointing to "<ip>:+<pid>", removed on clean exit
+//            (`.parentlock` is left behind after exit, so it can't be used for detection)
+//   macOS:   fcntl lock on `.parentlock`, released when Firefox exits (there is no `lock` symlink)
+//   Windows: `parent.lock` file, kept open without sharing
+pub fn is_firefox_profile_locked(profile_dir: &Path) -> bool {
+    if cfg!(target_os = "windows") {
+        return is_file_open_exclusively(profile_dir.join("parent.lock").as_path());
+    }
+    if cfg!(target_os = "macos") {
+        return is_fcntl_lock_held(profile_dir.join(".parentlock").as_path());
+    }
+    return is_symlink_lock_held(profile_dir.join("lock").as_path());
+}
+
+// Chromium locks the user data directory while it's running:
+//   Linux/macOS: `SingletonLock` symlink pointing to "<hostname>-<pid>"
+//   Windows:     `lockfile` file, kept open without sharing
+pub fn is_chromium_user_data_dir_locked(user_data_dir: &Path) -> bool {
+    if cfg!(target_os = "windows") {
+        return is_file_open_exclusively(user_data_dir.join("lockfile").as_path());
+    }
+    return is_symlink_lock_held(user_data_dir.join("SingletonLock").as_path());
+}
+
+fn is_symlink_lock_held(lock_symlink: &Path) -> bool {
+    if fs::symlink_metadata(lock_symlink).is_err() {
+        return false;
+    }
+
+    // symlink of a crashed browser stays behind
+    let lock_target = fs::read_link(lock_symlink)
+        .map(|target| target.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let is_stale = !is_lock_owner_alive(lock_target.as_str());
+    if is_stale {
+        debug!(
+            "Ignoring stale lock {} -> {}",
+            lock_symlink.display(),
+            lock_target
+        );
+    }
+    return !is_stale;
+}
+
+// "127.0.1.1:+12345" (Firefox) or "hostname-12345" (Chromium) -> whether process 12345 is running;
+// unknown owner counts as alive
+fn is_lock_owner_alive(lock_target: &str) -> bool {
+    let pid_maybe = lock_target
+        .rsplit(['+', '-'])
+        .next()
+        .and_then(|pid| pid.parse::<u32>().ok());
+
+    return match pid_maybe {
+        Some(pid) => is_process_running(pid),
+        None => true,
+    };
+}
+
+#[cfg(target_os = "macos")]
+fn is_process_running(pid: u32) -> bool {
+    // 0 and values which wrap around to negative pids would signal a whole process group
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => pid,
+        _ => return false,
+    };
+
+    // signal 0 only checks whether the process exists
+    let result = unsafe { libc::kill(pid, 0) };
+    return result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_process_running(pid: u32) -> bool {
+    return Path::new("/proc").join(pid.to_string()).exists();
+}
+
+#[cfg(target_os = "windows")]
+fn is_file_open_exclusively(file_path: &Path) -> bool {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    // ERROR_SHARING_VIOLATION
+    const SHARING_VIOLATION: i32 = 32;
+
+    let result = fs::OpenOptions::new()
+        .read(true)
+        .share_mode(0)
+        .open(file_path);
+    return match result {
+        Ok(_) => false,
+        Err(e) => e.raw_os_error() == Some(SHARING_VIOLATION),
+    };
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_file_open_exclusively(_file_path: &Path) -> bool {
+    return false;
+}
+
+// whether another process holds an fcntl (or flock) lock on the file
+#[cfg(target_os = "macos")]
+fn is_fcntl_lock_held(lock_file: &Path) -> bool {
+    use std::os::unix::io::AsRawFd;
+
+    let file = match fs::File::open(lock_file) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+
+    // asks which lock would block write-locking the whole file
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = libc::F_WRLCK as libc::c_short;
+    lock.l_whence = libc::SEEK_SET as libc::c_short;
+    let result = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETLK, &mut lock) };
+    return result == 0 && lock.l_type != libc::F_UNLCK as libc::c_short;
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_fcntl_lock_held(_lock_file: &Path) -> bool {
+    return false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_dir;
+
+    #[test]
+    fn test_unlocked_profile() {
+        let profile_dir = create_test_dir("profile-lock", "unlocked");
+        // left behind after Firefox exits
+        fs::write(profile_dir.join(".parentlock"), "").unwrap();
+
+        assert!(!is_firefox_profile_locked(profile_dir.as_path()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_locked_profile() {
+        let profile_dir = create_test_dir("profile-lock", "locked");
+        // the lock of this test process
+        let lock_target = format!("127.0.1.1:+{}", std::process::id());
+        std::os::unix::fs::symlink(lock_target, profile_dir.join("lock")).unwrap();
+
+        assert!(is_firefox_profile_locked(profile_dir.as_path()));
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_locked_profile_macos() {
+        use std::os::unix::io::AsRawFd;
+
+        let profile_dir = create_test_dir("profile-lock", "locked-macos");
+        let parentlock = profile_dir.join(".parentlock");
+        fs::write(parentlock.as_path(), "").unwrap();
+        assert!(!is_firefox_profile_locked(profile_dir.as_path()));
+
+        // a process doesn't conflict with its own fcntl locks, so a child process holds it
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .open(parentlock.as_path())
+            .unwrap();
+        let mut pipe_fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(pipe_fds.as_mut_ptr()) }, 0);
+        let pid = unsafe { libc::fork() };
+        if pid == 0 {
+            unsafe {
+                let mut lock: libc::flock = std::mem::zeroed();
+                lock.l_type = libc::F_WRLCK as libc::c_short;
+                lock.l_whence = libc::SEEK_SET as libc::c_short;
+                libc::fcntl(file.as_raw_fd(), libc::F_SETLK, &lock);
+                libc::write(pipe_fds[1], b"x".as_ptr() as *const libc::c_void, 1);
+                libc::pause();
+                libc::_exit(0);
+            }
+        }
+
+        // wait until the child holds the lock
+        let mut buf = [0u8; 1];
+        unsafe { libc::read(pipe_fds[0], buf.as_mut_ptr() as *mut libc::c_void, 1) };
+        let is_locked_while_running = is_firefox_profile_locked(profile_dir.as_path());
+
+        unsafe {
+            libc::kill(pid, libc::SIGKILL);
+            libc::waitpid(pid, std::ptr::null_mut(), 0);
+        }
+        let is_locked_after_exit = is_firefox_profile_locked(profile_dir.as_path());
+
+        assert!(is_locked_while_running);
+        assert!(!is_locked_after_exit);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stale_lock() {
+        let profile_dir = create_test_dir("profile-lock", "stale");
+        std::os::unix::fs::symlink("127.0.1.1:+4294967295", profile_dir.join("lock")).unwrap();
+
+        assert!(!is_firefox_profile_locked(profile_dir.as_path()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_chromium_lock() {
+        let user_data_dir = create_test_dir("profile-lock", "chromium");
+        assert!(!is_chromium_user_data_dir_locked(user_data_dir.as_path()));
+
+        let lock_target = format!("my-host-{}", std::process::id());
+        std::os::unix::fs::symlink(lock_target, user_data_dir.join("SingletonLock")).unwrap();
+        assert!(is_chromium_user_data_dir_locked(user_data_dir.as_path()));
+
+        // left behind by a crashed browser
+        let stale_user_data_dir = create_test_dir("profile-lock", "chromium-stale");
+        let stale_lock = stale_user_data_dir.join("SingletonLock");
+        std::os::unix::fs::symlink("my-host-4294967295", stale_lock).unwrap();
+        assert!(!is_chromium_user_data_dir_locked(stale_user_data_dir.as_path()));
+    }
+
+    #[test]
+    fn test_is_lock_owner_alive() {
+        assert!(is_lock_owner_alive("unknown"));
+        assert!(!is_lock_owner_alive("127.0.1.1:+4294967295"));
+        assert!(!is_lock_owner_alive("my-host-4294967295"));
+    }
+}
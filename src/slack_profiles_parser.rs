@@ -74,6 +74,16 @@ pub fn find_slack_profiles(
                 //slack_gov_workspace_url_pattern,
                 //app_url_pattern,
             ],
+            locked_to_other_install: false,
+            profile_container_color: None,
+            profile_container_icon: None,
+            profile_account_email: None,
+            profile_account_domain: None,
+            profile_last_used: None,
+            profile_is_browser_default: false,
+            profile_dir: None,
+            profile_launch_by_dir: false,
+            profile_is_temporary: false,
         })
     }
     return browser_profiles;
@@ -0,0 +1,209 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{Map, Value};
+use tracing::{debug, info, warn};
+
+use crate::{paths, profile_lock, InstalledBrowserProfile};
+
+// Throwaway profiles for opening a link in a clean browser, e.g to reproduce bugs.
+// Every launch gets a fresh directory, which is removed once the browser doesn't use it anymore.
+
+// not a valid profile name or directory, so it can't clash with the id of a real profile
+pub const TEMPORARY_PROFILE_CLI_ARG_VALUE: &str = ":temporary";
+
+// browser might not have locked a just created profile yet
+const MIN_AGE_BEFORE_REMOVAL: Duration = Duration::from_secs(60);
+
+pub fn temporary_profile() -> InstalledBrowserProfile {
+    return InstalledBrowserProfile {
+        profile_cli_arg_value: TEMPORARY_PROFILE_CLI_ARG_VALUE.to_string(),
+        profile_cli_container_name: None,
+        profile_name: "Temporary profile".to_string(),
+        profile_icon: None,
+        profile_restricted_url_patterns: vec![],
+        locked_to_other_install: false,
+        profile_container_color: None,
+        profile_container_icon: None,
+        profile_account_email: None,
+        profile_account_domain: None,
+        profile_last_used: None,
+        profile_is_browser_default: false,
+        profile_dir: None,
+        profile_launch_by_dir: false,
+        profile_is_temporary: true,
+    };
+}
+
+fn get_temporary_profiles_dir() -> PathBuf {
+    return paths::get_runtime_dir().join("temporary-profiles");
+}
+
+// fresh `--user-data-dir`, prefs are written to its default profile's `Preferences`;
+// on dry run only the path is returned, e.g to show the command without launching it
+pub fn create_chromium_temporary_profile(prefs: &Map<String, Value>, dry_run: bool) -> PathBuf {
+    let temporary_profiles_dir = get_temporary_profiles_dir();
+    if dry_run {
+        return new_temporary_profile_dir(temporary_profiles_dir.as_path(), "chromium");
+    }
+    let user_data_dir = create_temporary_profile_dir(temporary_profiles_dir.as_path(), "chromium");
+
+    // skips the welcome page and the default browser prompt
+    write_file(user_data_dir.join("First Run").as_path(), "");
+
+    if !prefs.is_empty() {
+        let default_profile_dir = user_data_dir.join("Default");
+        fs::create_dir_all(default_profile_dir.as_path()).ok();
+        let preferences = Value::Object(prefs.clone()).to_string();
+        write_file(
+            default_profile_dir.join("Preferences").as_path(),
+            preferences.as_str(),
+        );
+    }
+
+    return user_data_dir;
+}
+
+// fresh `--profile` dir, prefs are written to its `user.js`; on dry run only the path is returned
+pub fn create_firefox_temporary_profile(prefs: &Map<String, Value>, dry_run: bool) -> PathBuf {
+    let temporary_profiles_dir = get_temporary_profiles_dir();
+    if dry_run {
+        return new_temporary_profile_dir(temporary_profiles_dir.as_path(), "firefox");
+    }
+    let profile_dir = create_temporary_profile_dir(temporary_profiles_dir.as_path(), "firefox");
+
+    if !prefs.is_empty() {
+        write_file(profile_dir.join("user.js").as_path(), to_user_js(prefs).as_str());
+    }
+
+    return profile_dir;
+}
+
+// e.g "firefox-1700000000123-4567"
+fn new_temporary_profile_dir(temporary_profiles_dir: &Path, kind: &str) -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    return temporary_profiles_dir.join(format!("{}-{}-{}", kind, millis, std::process::id()));
+}
+
+fn create_temporary_profile_dir(temporary_profiles_dir: &Path, kind: &str) -> PathBuf {
+    let profile_dir = new_temporary_profile_dir(temporary_profiles_dir, kind);
+
+    // the browser creates the dir itself if this fails
+    if let Err(e) = fs::create_dir_all(profile_dir.as_path()) {
+        warn!(
+            "Could not create temporary profile {}: {}",
+            profile_dir.display(),
+            e
+        );
+    }
+    info!("Temporary profile {}", profile_dir.display());
+
+    return profile_dir;
+}
+
+fn write_file(file_path: &Path, content: &str) {
+    if let Err(e) = fs::write(file_path, content) {
+        warn!("Could not write {}: {}", file_path.display(), e);
+    }
+}
+
+// {"browser.startup.homepage": "about:blank"} -> user_pref("browser.startup.homepage", "about:blank");
+fn to_user_js(prefs: &Map<String, Value>) -> String {
+    return prefs
+        .iter()
+        .map(|(name, value)| format!("user_pref({}, {});\n", Value::from(name.as_str()), value))
+        .collect();
+}
+
+// removes the temporary profiles of browsers which have exited
+pub fn remove_unused_temporary_profiles() {
+    remove_unused_temporary_profiles_in(
+        get_temporary_profiles_dir().as_path(),
+        MIN_AGE_BEFORE_REMOVAL,
+    );
+}
+
+fn remove_unused_temporary_profiles_in(temporary_profiles_dir: &Path, min_age: Duration) {
+    let Ok(entries) = fs::read_dir(temporary_profiles_dir) else {
+        return;
+    };
+
+    for profile_dir in entries.flatten().map(|entry| entry.path()) {
+        let age_maybe = fs::metadata(profile_dir.as_path())
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+        if age_maybe.is_none_or(|age| age < min_age) {
+            debug!("Keeping new temporary profile {}", profile_dir.display());
+            continue;
+        }
+
+        let is_running = profile_lock::is_firefox_profile_locked(profile_dir.as_path())
+            || profile_lock::is_chromium_user_data_dir_locked(profile_dir.as_path());
+        if is_running {
+            debug!("Keeping running temporary profile {}", profile_dir.display());
+            continue;
+        }
+
+        info!("Removing temporary profile {}", profile_dir.display());
+        if let Err(e) = fs::remove_dir_all(profile_dir.as_path()) {
+            warn!(
+                "Could not remove temporary profile {}: {}",
+                profile_dir.display(),
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_dir;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_user_js() {
+        let prefs = json!({
+            "browser.startup.homepage": "about:blank",
+            "devtools.toolbox.host": "window",
+            "dom.webnotifications.enabled": false,
+            "network.cookie.cookieBehavior": 5,
+        });
+
+        assert_eq!(
+            to_user_js(prefs.as_object().unwrap()),
+            "user_pref(\"browser.startup.homepage\", \"about:blank\");\n\
+             user_pref(\"devtools.toolbox.host\", \"window\");\n\
+             user_pref(\"dom.webnotifications.enabled\", false);\n\
+             user_pref(\"network.cookie.cookieBehavior\", 5);\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_unused_temporary_profiles() {
+        let test_dir = create_test_dir("temporary-profiles", "remove");
+        let exited_profile_dir = create_temporary_profile_dir(test_dir.as_path(), "firefox");
+        let running_profile_dir = create_temporary_profile_dir(test_dir.as_path(), "chromium");
+        // same lock on Linux and macOS, unlike the one of Firefox
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(
+            format!("my-host-{}", std::process::id()),
+            running_profile_dir.join("SingletonLock"),
+        )
+        .unwrap();
+
+        // just created profiles are kept
+        remove_unused_temporary_profiles_in(test_dir.as_path(), MIN_AGE_BEFORE_REMOVAL);
+        assert!(exited_profile_dir.exists());
+
+        remove_unused_temporary_profiles_in(test_dir.as_path(), Duration::ZERO);
+        assert!(!exited_profile_dir.exists());
+        #[cfg(unix)]
+        assert!(running_profile_dir.exists());
+    }
+}
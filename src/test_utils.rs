@@ -0,0 +1,35 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::PathBuf;
+
+// directory for the files of a test, removed when the test finishes (also when it fails)
+pub(crate) struct TestDir {
+    path: PathBuf,
+}
+
+impl Deref for TestDir {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        return &self.path;
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(self.path.as_path()).ok();
+    }
+}
+
+// e.g "/tmp/browsers-firefox-test-locked-1234"
+pub(crate) fn create_test_dir(prefix: &str, name: &str) -> TestDir {
+    let path = std::env::temp_dir().join(format!(
+        "browsers-{}-test-{}-{}",
+        prefix,
+        name,
+        std::process::id()
+    ));
+    fs::remove_dir_all(path.as_path()).ok();
+    fs::create_dir_all(path.as_path()).unwrap();
+    return TestDir { path: path };
+}
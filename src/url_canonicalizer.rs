@@ -0,0 +1,216 @@
+use url::Url;
+
+use crate::utils::MobileHostMapping;
+
+// mobile host -> desktop host; a mapping also applies to subdomains of the mobile host,
+// e.g "en.m.wikipedia.org" -> "en.wikipedia.org"
+const BUILTIN_MOBILE_HOSTS: [(&str, &str); 12] = [
+    ("m.wikipedia.org", "wikipedia.org"),
+    ("m.wiktionary.org", "wiktionary.org"),
+    ("m.wikimedia.org", "wikimedia.org"),
+    ("m.youtube.com", "www.youtube.com"),
+    ("m.facebook.com", "www.facebook.com"),
+    ("mobile.facebook.com", "www.facebook.com"),
+    ("mobile.twitter.com", "twitter.com"),
+    ("mobile.x.com", "x.com"),
+    ("m.imdb.com", "www.imdb.com"),
+    ("m.reddit.com", "www.reddit.com"),
+    ("m.twitch.tv", "www.twitch.tv"),
+    ("mobile.nytimes.com", "www.nytimes.com"),
+];
+
+// Returns the publisher url for links served via Google AMP viewer or AMP cache,
+// or None if the url is not an AMP url
+pub fn canonicalize_amp_url(url: &Url) -> Option<String> {
+    let host = url.host_str()?.to_lowercase();
+
+    // https://www.google.com/amp/s/example.com/article
+    let is_google_amp_viewer = is_google_host(host.as_str()) && url.path().starts_with("/amp/");
+    if is_google_amp_viewer {
+        let rest = &url.path()["/amp/".len()..];
+        return amp_path_to_url(rest, url);
+    }
+
+    // https://example-com.cdn.ampproject.org/c/s/example.com/article
+    // https://example-com.cdn.ampproject.org/v/s/example.com/article
+    let is_amp_cache = host == "cdn.ampproject.org" || host.ends_with(".cdn.ampproject.org");
+    if is_amp_cache {
+        let path = url.path();
+        let rest_maybe = path
+            .strip_prefix("/c/")
+            .or_else(|| path.strip_prefix("/v/"));
+        return rest_maybe.and_then(|rest| amp_path_to_url(rest, url));
+    }
+
+    return None;
+}
+
+// Google search domains are all of the form google.com, google.<cc>, google.co.<cc> or
+// google.com.<cc>, where <cc> is a country code (see https://www.google.com/supported_domains);
+// so e.g "google.example.com" is not Google
+fn is_google_host(host: &str) -> bool {
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let Some(suffix) = host.strip_prefix("google.") else {
+        return false;
+    };
+
+    let is_country_code =
+        |label: &str| label.len() == 2 && label.chars().all(|c| c.is_ascii_lowercase());
+    let country_code = suffix
+        .strip_prefix("co.")
+        .or_else(|| suffix.strip_prefix("com."))
+        .unwrap_or(suffix);
+    return suffix == "com" || is_country_code(country_code);
+}
+
+// "s/example.com/article" -> "https://example.com/article"
+// "example.com/article"   -> "http://example.com/article"
+fn amp_path_to_url(amp_path: &str, amp_url: &Url) -> Option<String> {
+    let (scheme, host_and_path) = match amp_path.strip_prefix("s/") {
+        Some(rest) => ("https", rest),
+        None => ("http", amp_path),
+    };
+    if host_and_path.is_empty() {
+        return None;
+    }
+
+    let mut publisher_url_str = format!("{}://{}", scheme, host_and_path);
+    if let Some(query) = amp_url.query() {
+        publisher_url_str.push('?');
+        publisher_url_str.push_str(query);
+    }
+    if let Some(fragment) = amp_url.fragment() {
+        publisher_url_str.push('#');
+        publisher_url_str.push_str(fragment);
+    }
+
+    // make sure we produced something sensible
+    let publisher_url = Url::parse(publisher_url_str.as_str()).ok()?;
+    publisher_url.host_str()?;
+    return Some(publisher_url.to_string());
+}
+
+// Returns the desktop url for a known mobile host, or None if there is no mapping for the host.
+// User-defined mappings take precedence over the built-in ones.
+pub fn canonicalize_mobile_url(url: &Url, user_mappings: &[MobileHostMapping]) -> Option<String> {
+    let host = url.host_str()?.to_lowercase();
+
+    let user_mappings_iter = user_mappings
+        .iter()
+        .map(|m| (m.mobile_host.as_str(), m.desktop_host.as_str()));
+    let builtin_mappings_iter = BUILTIN_MOBILE_HOSTS.iter().copied();
+
+    let desktop_host = user_mappings_iter.chain(builtin_mappings_iter).find_map(
+        |(mobile_host, desktop_host)| map_mobile_host(host.as_str(), mobile_host, desktop_host),
+    )?;
+
+    let mut desktop_url = url.clone();
+    desktop_url.set_host(Some(desktop_host.as_str())).ok()?;
+    return Some(desktop_url.to_string());
+}
+
+fn map_mobile_host(host: &str, mobile_host: &str, desktop_host: &str) -> Option<String> {
+    let mobile_host = mobile_host.to_lowercase();
+    if host == mobile_host {
+        return Some(desktop_host.to_string());
+    }
+
+    let subdomain = host.strip_suffix(format!(".{}", mobile_host).as_str())?;
+    return Some(format!("{}.{}", subdomain, desktop_host));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amp(url_str: &str) -> Option<String> {
+        return canonicalize_amp_url(&Url::parse(url_str).unwrap());
+    }
+
+    fn mobile(url_str: &str, user_mappings: &[MobileHostMapping]) -> Option<String> {
+        return canonicalize_mobile_url(&Url::parse(url_str).unwrap(), user_mappings);
+    }
+
+    #[test]
+    fn test_google_amp_viewer() {
+        assert_eq!(
+            amp("https://www.google.com/amp/s/www.example.com/news/article.amp?id=1#top"),
+            Some("https://www.example.com/news/article.amp?id=1#top".to_string())
+        );
+        assert_eq!(
+            amp("https://www.google.co.uk/amp/example.com/article"),
+            Some("http://example.com/article".to_string())
+        );
+        assert_eq!(
+            amp("https://google.com.au/amp/s/example.com/article"),
+            Some("https://example.com/article".to_string())
+        );
+        assert_eq!(amp("https://www.google.com/search?q=amp"), None);
+        assert_eq!(amp("https://www.google.com/amp/s/"), None);
+    }
+
+    #[test]
+    fn test_amp_cache() {
+        assert_eq!(
+            amp("https://www-example-com.cdn.ampproject.org/c/s/www.example.com/article"),
+            Some("https://www.example.com/article".to_string())
+        );
+        assert_eq!(
+            amp("https://www-example-com.cdn.ampproject.org/v/s/www.example.com/article?amp_js_v=0.1"),
+            Some("https://www.example.com/article?amp_js_v=0.1".to_string())
+        );
+        assert_eq!(
+            amp("https://cdn.ampproject.org/c/example.com/"),
+            Some("http://example.com/".to_string())
+        );
+        assert_eq!(amp("https://cdn.ampproject.org/v0.js"), None);
+    }
+
+    #[test]
+    fn test_not_amp() {
+        assert_eq!(amp("https://example.com/amp/s/example.org/"), None);
+        // only Google's own domains are unwrapped
+        assert_eq!(amp("https://google.evil.com/amp/s/example.org/"), None);
+        assert_eq!(amp("https://www.google.com.evil.com/amp/s/example.org/"), None);
+        assert_eq!(amp("https://google.co.evil/amp/s/example.org/"), None);
+        assert_eq!(amp("https://notgoogle.com/amp/s/example.org/"), None);
+    }
+
+    #[test]
+    fn test_builtin_mobile_hosts() {
+        assert_eq!(
+            mobile("https://en.m.wikipedia.org/wiki/Rust", &[]),
+            Some("https://en.wikipedia.org/wiki/Rust".to_string())
+        );
+        assert_eq!(
+            mobile("https://m.youtube.com/watch?v=abc", &[]),
+            Some("https://www.youtube.com/watch?v=abc".to_string())
+        );
+        assert_eq!(mobile("https://m.example.com/", &[]), None);
+        assert_eq!(mobile("https://www.youtube.com/", &[]), None);
+    }
+
+    #[test]
+    fn test_user_mobile_hosts() {
+        let user_mappings = vec![
+            MobileHostMapping {
+                mobile_host: "m.example.com".to_string(),
+                desktop_host: "www.example.com".to_string(),
+            },
+            MobileHostMapping {
+                mobile_host: "m.youtube.com".to_string(),
+                desktop_host: "youtube.com".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            mobile("https://m.example.com/a?b=c", &user_mappings),
+            Some("https://www.example.com/a?b=c".to_string())
+        );
+        // user mapping wins over built-in one
+        assert_eq!(
+            mobile("https://m.youtube.com/", &user_mappings),
+            Some("https://youtube.com/".to_string())
+        );
+    }
+}
@@ -4,8 +4,12 @@ use globset::{GlobBuilder, GlobMatcher};
 use tracing::debug;
 use url::Url;
 
+use crate::mailto_url_parser;
+
 /// [scheme://]hostname[/path][?query][#fragment]
 /// [*://]**[/**][?**][#*]
+///
+/// `?**name=value**` matches when the query has the parameter anywhere, e.g `?a=1&name=value`
 #[derive(Debug, PartialEq)]
 pub struct UrlMatcher {
     scheme: String,
@@ -22,6 +26,9 @@ pub struct UrlGlobMatcher {
     path: GlobMatcher,
     query: GlobMatcher,
     fragment: GlobMatcher,
+    // only explicit mailto rules match by the recipient's domain,
+    // so that e.g "example.com" doesn't match "mailto:someone@example.com"
+    is_mailto: bool,
 }
 
 impl UrlGlobMatcher {
@@ -35,7 +42,8 @@ impl UrlGlobMatcher {
 
         // "name=ferret&color=purple" -> "name=ferret/color=purple"
         let query_with_slashes = url_matcher.query.replace("&", "/");
-        let query_matcher = Self::str_to_glob(query_with_slashes.as_str(), "query", true);
+        let query_glob = Self::expand_query_parameters_anywhere(query_with_slashes.as_str());
+        let query_matcher = Self::str_to_glob(query_glob.as_str(), "query", true);
         let fragment_matcher = Self::str_to_glob(url_matcher.fragment.as_str(), "fragment", false);
 
         Self {
@@ -44,7 +52,37 @@ impl UrlGlobMatcher {
             path: path_matcher,
             query: query_matcher,
             fragment: fragment_matcher,
+            is_mailto: url_matcher.scheme.eq_ignore_ascii_case("mailto"),
+        }
+    }
+
+    // "**a=1**" -> "{a=1,a=1/**,**/a=1,**/a=1/**}",
+    // because "**" only matches any parameters as a whole component, e.g "**/a=1/**",
+    // and that wouldn't match "a=1" as the first or last parameter
+    fn expand_query_parameters_anywhere(query_with_slashes: &str) -> String {
+        let rest_after_any_maybe = query_with_slashes
+            .strip_prefix("**")
+            .filter(|rest| !rest.is_empty() && !rest.starts_with('/'));
+        let rest = rest_after_any_maybe.unwrap_or(query_with_slashes);
+        let parameters_before_any_maybe = rest
+            .strip_suffix("**")
+            .filter(|parameters| !parameters.is_empty() && !parameters.ends_with('/'));
+        let parameters = parameters_before_any_maybe.unwrap_or(rest);
+
+        // alternatives can't be nested
+        if parameters.contains(['{', '}']) {
+            return query_with_slashes.to_string();
         }
+
+        return match (
+            rest_after_any_maybe.is_some(),
+            parameters_before_any_maybe.is_some(),
+        ) {
+            (true, true) => format!("{{{0},{0}/**,**/{0},**/{0}/**}}", parameters),
+            (true, false) => format!("{{{0},**/{0}}}", parameters),
+            (false, true) => format!("{{{0},{0}/**}}", parameters),
+            (false, false) => query_with_slashes.to_string(),
+        };
     }
 
     fn str_to_glob(pattern: &str, name: &str, literal_separator: bool) -> GlobMatcher {
@@ -59,17 +97,26 @@ impl UrlGlobMatcher {
 
     fn to_target_url(&self, url: &Url) -> TargetUrl {
         let scheme = url.scheme();
-        let host = url
-            .host_str()
-            .unwrap_or_else(|| panic!("no host found from url: {}", url.as_str()));
-        let path = url.path();
+        // mailto urls are matched by the recipient's domain, e.g "mailto:a@acme.com" -> "acme.com";
+        // other urls like "obsidian:open" have no host
+        let host = if scheme == "mailto" && self.is_mailto {
+            mailto_url_parser::get_recipient_domain(url).unwrap_or_default()
+        } else {
+            url.host_str().unwrap_or("").to_string()
+        };
+        // http(s) paths always start with "/", make custom schemes match "/**" the same way
+        let path = if url.path().starts_with('/') {
+            url.path().to_string()
+        } else {
+            format!("/{}", url.path())
+        };
         let query = url.query().unwrap_or("");
         let fragment = url.fragment().unwrap_or("");
 
         return TargetUrl {
             scheme: scheme.to_string(),
-            hostname: host.to_string(),
-            path: path.to_string(),
+            hostname: host,
+            path: path,
             query: query.to_string(),
             fragment: fragment.to_string(),
         };
@@ -404,4 +451,53 @@ mod tests {
             .url_str_matches("https://beginning.of.something.great/v2/matches/everything");
         assert_eq!(matches, false);
     }
+
+    #[test]
+    fn test_url_matches_custom_scheme_without_host() {
+        let url_glob_matcher = to_url_matcher("obsidian://**").to_glob_matcher();
+        assert_eq!(
+            url_glob_matcher.url_str_matches("obsidian://open?vault=notes"),
+            true
+        );
+        assert_eq!(url_glob_matcher.url_str_matches("obsidian:open"), true);
+        assert_eq!(url_glob_matcher.url_str_matches("https://obsidian.md/"), false);
+
+        let mailto_matcher = to_url_matcher("example.com").to_glob_matcher();
+        assert_eq!(
+            mailto_matcher.url_str_matches("mailto:someone@example.com"),
+            false
+        );
+    }
+
+    #[test]
+    fn test_url_matches_query_parameter_anywhere() {
+        let url_glob_matcher = to_url_matcher("example.com/**?**a=1**").to_glob_matcher();
+        assert!(url_glob_matcher.url_str_matches("https://example.com/?a=1"));
+        assert!(url_glob_matcher.url_str_matches("https://example.com/?a=1&b=2"));
+        assert!(url_glob_matcher.url_str_matches("https://example.com/x?b=2&a=1"));
+        assert!(url_glob_matcher.url_str_matches("https://example.com/?b=2&a=1&c=3"));
+        assert!(!url_glob_matcher.url_str_matches("https://example.com/?a=10"));
+        assert!(!url_glob_matcher.url_str_matches("https://example.com/?ba=1"));
+        assert!(!url_glob_matcher.url_str_matches("https://example.com/"));
+
+        let url_glob_matcher = to_url_matcher("example.com/**?a=1**").to_glob_matcher();
+        assert!(url_glob_matcher.url_str_matches("https://example.com/?a=1&b=2"));
+        assert!(!url_glob_matcher.url_str_matches("https://example.com/?b=2&a=1"));
+
+        // plain wildcards keep working as before
+        let url_glob_matcher = to_url_matcher("example.com/**?a=*").to_glob_matcher();
+        assert!(url_glob_matcher.url_str_matches("https://example.com/?a=1"));
+        assert!(!url_glob_matcher.url_str_matches("https://example.com/?a=1&b=2"));
+    }
+
+    #[test]
+    fn test_url_matches_mailto_recipient_domain() {
+        let url_glob_matcher = to_url_matcher("mailto://acme.com").to_glob_matcher();
+        assert!(url_glob_matcher.url_str_matches("mailto:bob@acme.com?subject=hi"));
+        assert!(!url_glob_matcher.url_str_matches("mailto:bob@example.com"));
+
+        let url_glob_matcher = to_url_matcher("mailto://**").to_glob_matcher();
+        assert!(url_glob_matcher.url_str_matches("mailto:bob@acme.com"));
+        assert!(url_glob_matcher.url_str_matches("mailto:?subject=hi"));
+    }
 }
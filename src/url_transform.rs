@@ -0,0 +1,160 @@
+use serde::Deserialize;
+use url::form_urlencoded::byte_serialize;
+use url::Url;
+
+// Declarative url transform, configured per app in application-repository.toml, e.g:
+//   url_transform = { scheme = "workflowy" }
+//   url_transform = { template = "spotify:{0}:{1}" }
+//   url_transform = { template = "zoommtg://zoom.us/join?confno={1}&pwd={query:pwd}" }
+//
+// Template placeholders:
+//   {0}, {1}, ...  path segments
+//   {host}         host
+//   {path}         path without the leading slash
+//   {query}        full query string
+//   {query:name}   value of query parameter `name`
+//   {fragment}     fragment
+//
+// If a placeholder can't be filled (e.g the url has fewer path segments),
+// then the url is left unchanged.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct UrlTransform {
+    // replaces just the scheme, e.g "https://workflowy.com/#/abc" -> "workflowy://workflowy.com/#/abc"
+    scheme: Option<String>,
+
+    // builds a whole new url; takes precedence over `scheme`
+    template: Option<String>,
+}
+
+impl UrlTransform {
+    pub fn transform(&self, url_str: &str) -> Option<String> {
+        let url = Url::parse(url_str).ok()?;
+
+        if let Some(template) = &self.template {
+            return fill_template(template.as_str(), &url);
+        }
+
+        if let Some(scheme) = &self.scheme {
+            // string replacement instead of Url::set_scheme(),
+            // because url crate refuses to change special schemes (https) to custom ones
+            let without_scheme = &url.as_str()[url.scheme().len()..];
+            return Some(format!("{}{}", scheme, without_scheme));
+        }
+
+        return None;
+    }
+}
+
+fn fill_template(template: &str, url: &Url) -> Option<String> {
+    let mut result = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let end = start + rest[start..].find('}')?;
+        result.push_str(&rest[..start]);
+
+        let placeholder = &rest[start + 1..end];
+        let value = resolve_placeholder(placeholder, url)?;
+        result.push_str(value.as_str());
+
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+
+    return Some(result);
+}
+
+fn resolve_placeholder(placeholder: &str, url: &Url) -> Option<String> {
+    if let Ok(segment_index) = placeholder.parse::<usize>() {
+        return url
+            .path_segments()?
+            .filter(|segment| !segment.is_empty())
+            .nth(segment_index)
+            .map(|segment| segment.to_string());
+    }
+
+    if let Some(param_name) = placeholder.strip_prefix("query:") {
+        return url
+            .query_pairs()
+            .find(|(key, _)| key == param_name)
+            .map(|(_, value)| byte_serialize(value.as_bytes()).collect());
+    }
+
+    return match placeholder {
+        "host" => url.host_str().map(|host| host.to_string()),
+        "path" => Some(url.path().trim_start_matches('/').to_string()),
+        "query" => url.query().map(|query| query.to_string()),
+        "fragment" => url.fragment().map(|fragment| fragment.to_string()),
+        _ => None,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(template: &str) -> UrlTransform {
+        return UrlTransform {
+            scheme: None,
+            template: Some(template.to_string()),
+        };
+    }
+
+    #[test]
+    fn test_scheme_replacement() {
+        let transform = UrlTransform {
+            scheme: Some("workflowy".to_string()),
+            template: None,
+        };
+        assert_eq!(
+            transform.transform("https://workflowy.com/#/a1b2c3"),
+            Some("workflowy://workflowy.com/#/a1b2c3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_path_segment_template() {
+        let transform = template("spotify:{0}:{1}");
+        assert_eq!(
+            transform.transform("https://open.spotify.com/track/2QFvsZEjbketrpCgCNC9Zp?si=x"),
+            Some("spotify:track:2QFvsZEjbketrpCgCNC9Zp".to_string())
+        );
+        assert_eq!(
+            transform.transform("https://open.spotify.com/album/1A2B3C/"),
+            Some("spotify:album:1A2B3C".to_string())
+        );
+        // missing segment
+        assert_eq!(transform.transform("https://open.spotify.com/"), None);
+    }
+
+    #[test]
+    fn test_query_extraction() {
+        let transform = template("zoommtg://{host}/join?confno={1}&pwd={query:pwd}");
+        assert_eq!(
+            transform.transform("https://us02web.zoom.us/j/123456?pwd=a+b"),
+            Some("zoommtg://us02web.zoom.us/join?confno=123456&pwd=a+b".to_string())
+        );
+        assert_eq!(transform.transform("https://zoom.us/j/123456"), None);
+    }
+
+    #[test]
+    fn test_whole_parts() {
+        let transform = template("app://open/{path}?{query}#{fragment}");
+        assert_eq!(
+            transform.transform("https://example.com/a/b?c=d#e"),
+            Some("app://open/a/b?c=d#e".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unknown_placeholder() {
+        assert_eq!(template("x:{nope}").transform("https://example.com/"), None);
+        assert_eq!(template("x:{0").transform("https://example.com/a"), None);
+    }
+
+    #[test]
+    fn test_no_transform() {
+        assert_eq!(UrlTransform::default().transform("https://example.com/"), None);
+    }
+}
@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{fs, u32};
 
 use druid::image::imageops::FilterType;
@@ -8,7 +8,8 @@ use druid::image::{ImageFormat, Rgba};
 use druid::{image, Data};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, info};
+use serde_json::{Map, Value};
+use tracing::{debug, info, warn};
 
 #[cfg(target_os = "linux")]
 use crate::linux::linux_utils;
@@ -16,7 +17,10 @@ use crate::linux::linux_utils;
 use crate::macos::macos_utils;
 #[cfg(target_os = "windows")]
 use crate::windows::windows_utils;
-use crate::{paths, InstalledBrowser, SupportedAppRepository};
+use crate::{
+    basedir_profiles_parser, firefox_profiles_parser, paths, InstalledAppProfilesType,
+    InstalledBrowser, SupportedAppRepository,
+};
 
 #[cfg(target_os = "linux")]
 pub fn is_default_web_browser() -> bool {
@@ -58,12 +62,50 @@ pub struct Config {
     rules: Vec<ConfigRule>,
     ui: UIConfig,
     behavior: BehavioralConfig,
+    custom_launchers: Vec<CustomLauncherConfig>,
+    // mailto urls composed in a webmail, in a browser profile
+    webmail_targets: Vec<WebmailTargetConfig>,
+
+    // show Firefox profiles which are locked to another install of the same browser family
+    show_profiles_of_other_installs: bool,
+
+    // extra profile base dirs of qutebrowser, GNOME Web, etc
+    profile_base_dirs: Vec<ProfileBaseDirConfig>,
+
+    // extra url schemes to discover handler apps for (e.g obsidian, vscode, steam);
+    // replaces the repository's entry of the same scheme
+    url_schemes: Vec<UrlSchemeConfig>,
+
+    // extra `--user-data-dir` instances of Chromium-based apps, each with its own profiles
+    chromium_user_data_dirs: Vec<ChromiumUserDataDirConfig>,
+
+    // Firefox profile dirs which are not listed in profiles.ini, launched with `--profile <dir>`
+    firefox_profile_dirs: Vec<FirefoxProfileDirConfig>,
+
+    // throwaway profiles of Chromium- and Firefox-based browsers
+    temporary_profiles: TemporaryProfilesConfig,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[serde(default)]
 pub struct BehavioralConfig {
     pub unwrap_urls: bool,
+
+    // open AMP links (google.com/amp/s/..., *.cdn.ampproject.org/...) as the publisher url
+    pub canonicalize_amp_urls: bool,
+
+    // open mobile links (m.*, mobile.*) as desktop links
+    pub canonicalize_mobile_urls: bool,
+
+    // extends the built-in mobile -> desktop host table
+    pub mobile_hosts: Vec<MobileHostMapping>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MobileHostMapping {
+    pub mobile_host: String,
+    pub desktop_host: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -76,6 +118,9 @@ pub struct UIConfig {
     pub quit_on_lost_focus: bool,
 
     pub theme: ConfiguredTheme,
+
+    // order of profiles which are not in profile_order
+    pub profile_sort: ProfileSortMode,
 }
 
 impl Default for UIConfig {
@@ -84,10 +129,21 @@ impl Default for UIConfig {
             show_hotkeys: true,
             quit_on_lost_focus: false,
             theme: ConfiguredTheme::Auto,
+            profile_sort: ProfileSortMode::Alphabetical,
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Data, PartialEq)]
+pub enum ProfileSortMode {
+    // as found by the browser profile parsers, mostly by name
+    Alphabetical,
+    // by last use reported by the browser, within the profiles of each browser
+    MostRecentlyUsed,
+    // the profile the browser itself opens by default goes first
+    BrowserDefaultFirst,
+}
+
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, Data, PartialEq)]
 pub enum ConfiguredTheme {
     Auto,
@@ -100,6 +156,109 @@ pub enum ConfiguredTheme {
 pub struct ProfileAndOptions {
     pub profile: String,
     pub incognito: bool,
+    // open as a chromeless app window (Chromium-based browsers)
+    pub app_window: bool,
+}
+
+// user-defined opener which runs an arbitrary command, e.g a script or an ssh-forwarded browser
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct CustomLauncherConfig {
+    pub name: String,
+    pub icon: Option<String>,
+    // program and arguments; "{url}", "{host}" and "{path}" are replaced with parts of the url,
+    // url is appended as the last argument if there is no "{url}"
+    pub command: Vec<String>,
+    // offer this launcher only for matching urls (same syntax as rules)
+    pub restricted_url_patterns: Vec<String>,
+    pub working_dir: Option<String>,
+}
+
+// webmail which mailto urls are composed in, e.g Gmail in the work profile of Chrome
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct WebmailTargetConfig {
+    // shown in the list instead of the profile name, e.g "Work Gmail"
+    pub name: String,
+    // unique id of the browser profile, e.g "/usr/bin/google-chrome-stable#Profile 1"
+    pub profile: String,
+    pub preset: Option<WebmailPreset>,
+    // used without preset; "{to}", "{cc}", "{bcc}", "{subject}" and "{body}" are replaced with
+    // the fields of the mailto url, "{mailto}" with the whole mailto url
+    pub compose_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub enum WebmailPreset {
+    Gmail,
+    Outlook,
+    Fastmail,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct UrlSchemeConfig {
+    // without "://", e.g "obsidian"
+    pub scheme: String,
+    // http(s) domains which the handler apps are offered for (same syntax as rules);
+    // if empty, then the apps are offered only for urls of this scheme
+    pub restricted_domains: Vec<String>,
+}
+
+// profile of a browser which keeps each profile in a separate base directory
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct ProfileBaseDirConfig {
+    // e.g "org.qutebrowser.qutebrowser"
+    pub app_id: String,
+    // absolute path; the last component may contain a `*`, e.g "/home/me/qutebrowser/*"
+    pub dir: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct TemporaryProfilesConfig {
+    // offer a temporary profile for every supported browser; off by default, set in settings
+    pub enabled: bool,
+    // written to user.js, e.g { "devtools.toolbox.host": "window" }
+    pub firefox_prefs: Map<String, Value>,
+    // written to the Preferences file, nested as in Chromium's own,
+    // e.g { "browser": { "show_home_button": true } }
+    pub chromium_prefs: Map<String, Value>,
+}
+
+impl Default for TemporaryProfilesConfig {
+    fn default() -> Self {
+        TemporaryProfilesConfig {
+            enabled: false,
+            firefox_prefs: Map::new(),
+            chromium_prefs: Map::new(),
+        }
+    }
+}
+
+// Firefox profile outside of profiles.ini, e.g in a project folder
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct FirefoxProfileDirConfig {
+    // e.g "org.mozilla.firefox"
+    pub app_id: String,
+    // profile name shown in the list; the directory name if empty
+    pub name: String,
+    // absolute path
+    pub dir: String,
+}
+
+// Chromium-based app run with `--user-data-dir=<dir>`
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct ChromiumUserDataDirConfig {
+    // e.g "google-chrome" or "com.google.Chrome"
+    pub app_id: String,
+    // shown after the app name, e.g "Client A" -> "Google Chrome (Client A)"
+    pub name: String,
+    // absolute path
+    pub dir: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -205,6 +364,42 @@ impl Config {
     pub fn set_behavior(&mut self, behavior: BehavioralConfig) {
         self.behavior = behavior;
     }
+
+    pub fn get_custom_launchers(&self) -> &Vec<CustomLauncherConfig> {
+        return &self.custom_launchers;
+    }
+
+    pub fn get_webmail_targets(&self) -> &Vec<WebmailTargetConfig> {
+        return &self.webmail_targets;
+    }
+
+    pub fn get_show_profiles_of_other_installs(&self) -> bool {
+        return self.show_profiles_of_other_installs;
+    }
+
+    pub fn get_profile_base_dirs(&self) -> &Vec<ProfileBaseDirConfig> {
+        return &self.profile_base_dirs;
+    }
+
+    pub fn get_url_schemes(&self) -> &Vec<UrlSchemeConfig> {
+        return &self.url_schemes;
+    }
+
+    pub fn get_chromium_user_data_dirs(&self) -> &Vec<ChromiumUserDataDirConfig> {
+        return &self.chromium_user_data_dirs;
+    }
+
+    pub fn get_firefox_profile_dirs(&self) -> &Vec<FirefoxProfileDirConfig> {
+        return &self.firefox_profile_dirs;
+    }
+
+    pub fn get_temporary_profiles(&self) -> &TemporaryProfilesConfig {
+        return &self.temporary_profiles;
+    }
+
+    pub fn set_temporary_profiles_enabled(&mut self, enabled: bool) {
+        self.temporary_profiles.enabled = enabled;
+    }
 }
 
 pub struct OSAppFinder {
@@ -241,41 +436,184 @@ impl OSAppFinder {
     }
 
     pub fn get_installed_browsers(&self) -> Vec<InstalledBrowser> {
-        let schemes = vec![
-            ("figma", vec!["figma.com", "www.figma.com"]),
-            ("linear", vec!["linear.app"]),
-            ("mimestream", vec!["links.mimestream.com"]),
-            ("notion", vec!["notion.so", "www.notion.so"]),
-            ("slack", vec!["*.slack.com", "*.enterprise.slack.com"]),
-            ("spotify", vec!["open.spotify.com"]),
-            ("tg", vec!["t.me"]), // telegram
-            (
-                "zoommtg",
-                vec![
-                    "zoom.us",
-                    "eu01web.zoom.us",
-                    "us02web.zoom.us",
-                    "us03web.zoom.us",
-                    "us04web.zoom.us",
-                    "us05web.zoom.us",
-                    "us06web.zoom.us",
-                    "us07web.zoom.us",
-                ],
-            ),
-            ("workflowy", vec!["workflowy.com"]),
-            ("https", vec![]),
-        ];
-        let schemes_vec: Vec<(String, Vec<String>)> = schemes
+        let config = self.load_config();
+
+        let mut schemes_vec: Vec<(String, Vec<String>)> = self
+            .get_app_repository()
+            .get_schemes()
             .iter()
-            .map(|(scheme, domain_patterns)| {
+            .map(|scheme_config| {
                 (
-                    scheme.to_string(),
-                    domain_patterns.iter().map(|d| d.to_string()).collect(),
+                    scheme_config.get_scheme().to_string(),
+                    scheme_config.get_restricted_domains().clone(),
                 )
             })
             .collect();
 
-        return self.inner.get_installed_browsers(schemes_vec);
+        for url_scheme in config.get_url_schemes() {
+            let scheme = url_scheme.scheme.trim_end_matches("://").to_lowercase();
+            if scheme.is_empty() {
+                continue;
+            }
+
+            // apps of a custom scheme are not browsers, don't offer them for every url
+            let restricted_domains = if url_scheme.restricted_domains.is_empty() {
+                vec![format!("{}://**", scheme)]
+            } else {
+                url_scheme.restricted_domains.clone()
+            };
+
+            let existing_index_maybe = schemes_vec.iter().position(|(s, _)| s == &scheme);
+            if let Some(existing_index) = existing_index_maybe {
+                schemes_vec[existing_index].1 = restricted_domains;
+            } else {
+                schemes_vec.push((scheme, restricted_domains));
+            }
+        }
+
+        let installed_browsers = self.inner.get_installed_browsers(schemes_vec);
+        let mut installed_browsers = merge_duplicate_browsers(installed_browsers);
+
+        for installed_browser in installed_browsers.iter_mut() {
+            let supported_app = self
+                .get_app_repository()
+                .get_or_generate(installed_browser.bundle.as_str(), &vec![]);
+            if !supported_app.has_profile_base_dirs() {
+                continue;
+            }
+
+            let configured_base_dirs: Vec<PathBuf> = config
+                .get_profile_base_dirs()
+                .iter()
+                .filter(|base_dir| base_dir.app_id == installed_browser.bundle)
+                .map(|base_dir| PathBuf::from(base_dir.dir.as_str()))
+                .collect();
+            basedir_profiles_parser::add_configured_base_dirs(
+                &mut installed_browser.profiles,
+                &configured_base_dirs,
+            );
+        }
+
+        for installed_browser in installed_browsers.iter_mut() {
+            let supported_app = self
+                .get_app_repository()
+                .get_or_generate(installed_browser.bundle.as_str(), &vec![]);
+            if !supported_app.supports_profile_dir() {
+                continue;
+            }
+
+            // dirs which are also in profiles.ini are already found
+            let known_profile_dirs: Vec<String> = installed_browser
+                .profiles
+                .profiles
+                .iter()
+                .filter_map(|p| p.profile_dir.clone())
+                .collect();
+            let configured_profile_dirs: Vec<(String, PathBuf)> = config
+                .get_firefox_profile_dirs()
+                .iter()
+                .filter(|profile_dir| profile_dir.app_id == installed_browser.bundle)
+                .filter(|profile_dir| !known_profile_dirs.contains(&profile_dir.dir))
+                .map(|profile_dir| {
+                    (profile_dir.name.clone(), PathBuf::from(profile_dir.dir.as_str()))
+                })
+                .collect();
+
+            let standalone_profiles =
+                firefox_profiles_parser::find_standalone_firefox_profiles(&configured_profile_dirs);
+            if standalone_profiles.is_empty() {
+                continue;
+            }
+
+            let installed_profiles = &mut installed_browser.profiles;
+            if installed_profiles.profiles_type == InstalledAppProfilesType::PlaceholderProfiles {
+                installed_profiles.profiles_type = InstalledAppProfilesType::RealProfiles;
+                installed_profiles.profiles = vec![];
+            }
+            installed_profiles.profiles.extend(standalone_profiles);
+        }
+
+        let user_data_dir_browsers = self.find_chromium_user_data_dir_browsers(
+            &installed_browsers,
+            config.get_chromium_user_data_dirs(),
+        );
+        installed_browsers.extend(user_data_dir_browsers);
+
+        return installed_browsers;
+    }
+
+    // every extra user-data-dir is a separate app, with its own profiles
+    fn find_chromium_user_data_dir_browsers(
+        &self,
+        installed_browsers: &Vec<InstalledBrowser>,
+        user_data_dirs: &Vec<ChromiumUserDataDirConfig>,
+    ) -> Vec<InstalledBrowser> {
+        let mut user_data_dir_browsers: Vec<InstalledBrowser> = Vec::new();
+
+        for user_data_dir in user_data_dirs {
+            let browser_maybe = installed_browsers
+                .iter()
+                .find(|b| b.bundle == user_data_dir.app_id && b.user_data_dir.is_none());
+            let Some(browser) = browser_maybe else {
+                info!(
+                    "Skipping user data dir {}, because {} is not installed",
+                    user_data_dir.dir, user_data_dir.app_id
+                );
+                continue;
+            };
+
+            let supported_app = self
+                .get_app_repository()
+                .get_or_generate(browser.bundle.as_str(), &vec![]);
+            if !supported_app.supports_user_data_dir() {
+                warn!(
+                    "Skipping user data dir {}, because {} is not Chromium-based",
+                    user_data_dir.dir, user_data_dir.app_id
+                );
+                continue;
+            }
+            // the sandbox of the flatpak can't access arbitrary user data dirs
+            if browser.flatpak_app_id.is_some() {
+                warn!(
+                    "Skipping user data dir {}, because {} is a flatpak",
+                    user_data_dir.dir, user_data_dir.app_id
+                );
+                continue;
+            }
+
+            let instance_name = get_user_data_dir_instance_name(user_data_dir);
+            let instance_id = format!(
+                "{}-{}",
+                browser.bundle,
+                instance_name.replace(|c: char| !c.is_alphanumeric(), "_")
+            );
+
+            let profiles = supported_app.find_profiles_of_instance(
+                Path::new(browser.executable_path.as_str()),
+                Path::new(user_data_dir.dir.as_str()),
+                instance_id.as_str(),
+            );
+
+            user_data_dir_browsers.push(InstalledBrowser {
+                command: browser.command.clone(),
+                executable_path: browser.executable_path.clone(),
+                display_name: format!("{} ({})", browser.display_name, instance_name),
+                bundle: browser.bundle.clone(),
+                user_dir: user_data_dir.dir.clone(),
+                icon_path: browser.icon_path.clone(),
+                profiles: profiles,
+                restricted_domains: browser.restricted_domains.clone(),
+                flatpak_app_id: browser.flatpak_app_id.clone(),
+                snap_name: browser.snap_name.clone(),
+                user_data_dir: Some(user_data_dir.dir.clone()),
+                private_window_command: browser.private_window_command.clone(),
+                new_window_command: browser.new_window_command.clone(),
+                desktop_entry_icon: browser.desktop_entry_icon.clone(),
+                desktop_file_path: browser.desktop_file_path.clone(),
+            });
+        }
+
+        return user_data_dir_browsers;
     }
 
     pub(crate) fn get_app_repository(&self) -> &SupportedAppRepository {
@@ -350,6 +688,60 @@ impl OSAppFinder {
     }
 }
 
+// an app registered for several schemes (e.g https and slack) is found once per scheme;
+// keep it once, offered for the domains of all its schemes
+fn merge_duplicate_browsers(installed_browsers: Vec<InstalledBrowser>) -> Vec<InstalledBrowser> {
+    let mut merged_browsers: Vec<InstalledBrowser> = Vec::new();
+
+    for installed_browser in installed_browsers {
+        let existing_browser_maybe = merged_browsers.iter_mut().find(|b| {
+            b.bundle == installed_browser.bundle
+                && b.executable_path == installed_browser.executable_path
+        });
+
+        if let Some(existing_browser) = existing_browser_maybe {
+            debug!(
+                "Merging duplicate app {} ({})",
+                installed_browser.bundle, installed_browser.executable_path
+            );
+            existing_browser.restricted_domains = merge_restricted_domains(
+                &existing_browser.restricted_domains,
+                &installed_browser.restricted_domains,
+            );
+        } else {
+            merged_browsers.push(installed_browser);
+        }
+    }
+
+    return merged_browsers;
+}
+
+// empty means unrestricted, so it wins over any list of domains
+fn merge_restricted_domains(domains: &Vec<String>, other_domains: &Vec<String>) -> Vec<String> {
+    if domains.is_empty() || other_domains.is_empty() {
+        return vec![];
+    }
+
+    let mut merged_domains = domains.clone();
+    for domain in other_domains {
+        if !merged_domains.contains(domain) {
+            merged_domains.push(domain.clone());
+        }
+    }
+    return merged_domains;
+}
+
+// shown after the app name; the configured name, or else the name of the directory
+fn get_user_data_dir_instance_name(user_data_dir: &ChromiumUserDataDirConfig) -> String {
+    if !user_data_dir.name.is_empty() {
+        return user_data_dir.name.clone();
+    }
+    return Path::new(user_data_dir.dir.as_str())
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| user_data_dir.dir.clone());
+}
+
 /*const fn create_circular_mask_radius<const N: usize>() -> [[bool; N]; N] {
     let mut mask = [[true; N]; N];
 
@@ -454,3 +846,43 @@ pub fn save_as_circular(image_bytes: Vec<u8>, to_image_path: &Path) {
 
     debug!("WROTE TO : {:?}", png_file_path.as_path());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_strings(domains: &[&str]) -> Vec<String> {
+        return domains.iter().map(|domain| domain.to_string()).collect();
+    }
+
+    #[test]
+    fn test_merge_restricted_domains() {
+        assert_eq!(
+            merge_restricted_domains(
+                &to_strings(&["figma.com"]),
+                &to_strings(&["www.figma.com", "figma.com"])
+            ),
+            to_strings(&["figma.com", "www.figma.com"])
+        );
+        // registered for https as well, so it's offered for every url
+        assert!(merge_restricted_domains(&to_strings(&["*.slack.com"]), &vec![]).is_empty());
+        assert!(merge_restricted_domains(&vec![], &to_strings(&["*.slack.com"])).is_empty());
+    }
+
+    #[test]
+    fn test_get_user_data_dir_instance_name() {
+        let user_data_dir = |name: &str, dir: &str| ChromiumUserDataDirConfig {
+            app_id: "google-chrome".to_string(),
+            name: name.to_string(),
+            dir: dir.to_string(),
+        };
+        assert_eq!(
+            get_user_data_dir_instance_name(&user_data_dir("Client A", "/home/user/chrome-a")),
+            "Client A"
+        );
+        assert_eq!(
+            get_user_data_dir_instance_name(&user_data_dir("", "/home/user/chrome-a")),
+            "chrome-a"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::mailto_url_parser;
+use crate::utils::{WebmailPreset, WebmailTargetConfig};
+
+const GMAIL_COMPOSE_URL: &str =
+    "https://mail.google.com/mail/?view=cm&fs=1&to={to}&cc={cc}&bcc={bcc}&su={subject}&body={body}";
+// work and school accounts; personal accounts use outlook.live.com instead
+const OUTLOOK_COMPOSE_URL: &str = "https://outlook.office.com/mail/deeplink/compose?to={to}&cc={cc}&bcc={bcc}&subject={subject}&body={body}";
+const FASTMAIL_COMPOSE_URL: &str = "https://app.fastmail.com/action/compose/?mailto={mailto}";
+
+// used in configuration file to uniquely identify the webmail target;
+// the name is optional, so it's the profile and the webmail, e.g "webmail:<profile id>#Gmail"
+pub fn get_webmail_target_unique_id(webmail_target: &WebmailTargetConfig) -> String {
+    let webmail = match webmail_target.preset {
+        Some(preset) => format!("{:?}", preset),
+        None => webmail_target.compose_url.clone(),
+    };
+    return format!("webmail:{}#{}", webmail_target.profile, webmail);
+}
+
+fn get_compose_url_template(webmail_target: &WebmailTargetConfig) -> &str {
+    return match webmail_target.preset {
+        Some(WebmailPreset::Gmail) => GMAIL_COMPOSE_URL,
+        Some(WebmailPreset::Outlook) => OUTLOOK_COMPOSE_URL,
+        Some(WebmailPreset::Fastmail) => FASTMAIL_COMPOSE_URL,
+        None => webmail_target.compose_url.as_str(),
+    };
+}
+
+// mailto url -> compose url of the webmail; other urls are returned as is
+pub fn to_compose_url(webmail_target: &WebmailTargetConfig, url_str: &str) -> String {
+    let compose_url_template = get_compose_url_template(webmail_target);
+    let Some(mailto_url) = mailto_url_parser::parse_mailto_url(url_str) else {
+        return url_str.to_string();
+    };
+    if compose_url_template.is_empty() {
+        return url_str.to_string();
+    }
+
+    return compose_url_template
+        .replace("{to}", encode(mailto_url.to.join(",").as_str()).as_str())
+        .replace("{cc}", encode(mailto_url.cc.join(",").as_str()).as_str())
+        .replace("{bcc}", encode(mailto_url.bcc.join(",").as_str()).as_str())
+        .replace(
+            "{subject}",
+            encode(mailto_url.subject.unwrap_or_default().as_str()).as_str(),
+        )
+        .replace(
+            "{body}",
+            encode(mailto_url.body.unwrap_or_default().as_str()).as_str(),
+        )
+        .replace("{mailto}", encode(url_str).as_str());
+}
+
+// percent-encodes everything except unreserved characters (RFC 3986), so space is "%20";
+// webmails don't all decode "+" as space like in form urlencoding
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(format!("%{:02X}", byte).as_str());
+        }
+    }
+    return encoded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webmail_target(preset: Option<WebmailPreset>, compose_url: &str) -> WebmailTargetConfig {
+        return WebmailTargetConfig {
+            name: "Work mail".to_string(),
+            profile: "google-chrome#Profile 1".to_string(),
+            preset: preset,
+            compose_url: compose_url.to_string(),
+        };
+    }
+
+    #[test]
+    fn test_gmail_compose_url() {
+        let gmail = webmail_target(Some(WebmailPreset::Gmail), "");
+        assert_eq!(
+            to_compose_url(
+                &gmail,
+                "mailto:a@example.com,b@example.com?cc=c@example.com&subject=Hello%20there&body=Hi%0D%0A"
+            ),
+            "https://mail.google.com/mail/?view=cm&fs=1&to=a%40example.com%2Cb%40example.com\
+             &cc=c%40example.com&bcc=&su=Hello%20there&body=Hi%0D%0A"
+        );
+    }
+
+    #[test]
+    fn test_outlook_and_fastmail_compose_url() {
+        let outlook = webmail_target(Some(WebmailPreset::Outlook), "");
+        assert_eq!(
+            to_compose_url(&outlook, "mailto:a@example.com?subject=Hi"),
+            "https://outlook.office.com/mail/deeplink/compose?to=a%40example.com&cc=&bcc=&subject=Hi&body="
+        );
+
+        let fastmail = webmail_target(Some(WebmailPreset::Fastmail), "");
+        assert_eq!(
+            to_compose_url(&fastmail, "mailto:a@example.com?subject=Hi"),
+            "https://app.fastmail.com/action/compose/?mailto=mailto%3Aa%40example.com%3Fsubject%3DHi"
+        );
+    }
+
+    #[test]
+    fn test_get_webmail_target_unique_id() {
+        let gmail = webmail_target(Some(WebmailPreset::Gmail), "");
+        assert_eq!(
+            get_webmail_target_unique_id(&gmail),
+            "webmail:google-chrome#Profile 1#Gmail"
+        );
+
+        // targets without a name are still told apart
+        let mut unnamed_gmail = webmail_target(Some(WebmailPreset::Gmail), "");
+        unnamed_gmail.name = "".to_string();
+        let mut unnamed_outlook = webmail_target(Some(WebmailPreset::Outlook), "");
+        unnamed_outlook.name = "".to_string();
+        assert_ne!(
+            get_webmail_target_unique_id(&unnamed_gmail),
+            get_webmail_target_unique_id(&unnamed_outlook)
+        );
+
+        let custom = webmail_target(None, "https://mail.example.com/compose?rcpt={to}");
+        assert_eq!(
+            get_webmail_target_unique_id(&custom),
+            "webmail:google-chrome#Profile 1#https://mail.example.com/compose?rcpt={to}"
+        );
+    }
+
+    #[test]
+    fn test_custom_compose_url() {
+        let custom = webmail_target(None, "https://mail.example.com/compose?rcpt={to}&s={subject}");
+        assert_eq!(
+            to_compose_url(&custom, "mailto:?to=a@example.com&subject=caf%C3%A9"),
+            "https://mail.example.com/compose?rcpt=a%40example.com&s=caf%C3%A9"
+        );
+        // "+" is kept literally, not as a space
+        assert_eq!(
+            to_compose_url(&custom, "mailto:a+b@example.com?subject=1+1%20is%202"),
+            "https://mail.example.com/compose?rcpt=a%2Bb%40example.com&s=1%2B1%20is%202"
+        );
+        assert_eq!(
+            to_compose_url(&custom, "https://example.com/"),
+            "https://example.com/"
+        );
+    }
+}
@@ -275,7 +275,7 @@ impl OsHelper {
             .map(|path_perhaps| Path::new(path_perhaps))
             .unwrap_or(Path::new("unknown"));
 
-        let app_config_dir_abs = supported_app.get_app_config_dir_abs(false, false);
+        let app_config_dir_abs = supported_app.get_app_config_dir_abs(false, false, false);
 
         let profiles =
             supported_app.find_profiles(executable_path_best_guess.clone(), app_config_dir_abs);
@@ -289,6 +289,13 @@ impl OsHelper {
             icon_path: icon_path_str.clone(),
             profiles: profiles,
             restricted_domains: restricted_domains,
+            flatpak_app_id: None,
+            snap_name: None,
+            user_data_dir: None,
+            private_window_command: None,
+            new_window_command: None,
+            desktop_entry_icon: None,
+            desktop_file_path: None,
         };
         return Some(browser);
     }